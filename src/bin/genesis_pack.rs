@@ -0,0 +1,42 @@
+use std::{env, path::PathBuf, process};
+
+use genesis_engine::engine::assets::pack::{AssetPack, Compression, DEFAULT_ASSET_PACK};
+
+const USAGE: &str = "Usage: genesis_pack <assets directory> [output] [--no-compression]
+
+Packs every file under the assets directory into a genesis asset pack.
+The output defaults to `assets.gpak` inside the assets directory, which is
+where the engine looks for it.";
+
+fn main() {
+    let mut compression = Compression::Deflate;
+    let mut paths = Vec::new();
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--no-compression" => compression = Compression::None,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let (directory, output) = match paths.as_slice() {
+        [directory] => (directory.clone(), directory.join(DEFAULT_ASSET_PACK)),
+        [directory, output] => (directory.clone(), output.clone()),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match AssetPack::create(&directory, &output, compression) {
+        Ok(count) => println!("Packed {} files into {}", count, output.display()),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
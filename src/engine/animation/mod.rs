@@ -0,0 +1,316 @@
+use std::ops::Range;
+
+use super::graphics::atlas::SpriteSheet;
+
+///Shortest a frame can last, so frames without a duration can't stall
+///`SpriteAnimation::update` in an endless loop
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+///How a `SpriteAnimation` continues after its last frame:
+///- `Loop`: Starts over from the first frame
+///- `PingPong`: Plays the frames backwards down to the first one, then
+///forwards again
+///- `Once`: Stops on the last frame and reports itself as finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackMode {
+    #[default]
+    Loop,
+    PingPong,
+    Once,
+}
+
+///A frame of a `SpriteAnimation`: the region of the texture it shows, as
+///taken by `Sprite::with_uv_rect`, and how many seconds it lasts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationFrame {
+    pub uv_rect: [f32; 4],
+    pub duration: f32,
+}
+
+///Advances through the frames of a sprite sheet over time.
+///- `update`: Advances by the `delta_time` given to `Scene::update` and
+///returns the events of every frame entered, in order
+///- `uv_rect`: Region of the texture to draw the current frame with
+///
+///Events are attached to frames with `with_event` and fire each time the
+///frame is entered, including the first frame on the first update. They can
+///be anything cloneable, like a `&'static str` or an enum of sounds to play.
+///A delta longer than a whole cycle of a repeating animation skips the
+///extra cycles, so their events don't fire.
+pub struct SpriteAnimation<E = &'static str> {
+    frames: Vec<AnimationFrame>,
+    events: Vec<(usize, E)>,
+    mode: PlaybackMode,
+    current: usize,
+    elapsed: f32,
+    forward: bool,
+    started: bool,
+    finished: bool,
+}
+
+impl<E: Clone> SpriteAnimation<E> {
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        SpriteAnimation {
+            frames,
+            events: Vec::new(),
+            mode: PlaybackMode::default(),
+            current: 0,
+            elapsed: 0.0,
+            forward: true,
+            started: false,
+            finished: false,
+        }
+    }
+
+    ///Plays the frames in `frames` of `sheet`, each lasting
+    ///`frame_duration` seconds. Use `with_frame_duration` to change how
+    ///long single frames last.
+    pub fn from_sprite_sheet(sheet: &SpriteSheet, frames: Range<u32>, frame_duration: f32) -> Self {
+        Self::new(
+            frames
+                .map(|frame| AnimationFrame {
+                    uv_rect: sheet.uv_rect(frame),
+                    duration: frame_duration,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn with_mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    ///Makes the frame at `frame`, counting from the first frame of the
+    ///animation, last `duration` seconds
+    pub fn with_frame_duration(mut self, frame: usize, duration: f32) -> Self {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.duration = duration;
+        }
+        self
+    }
+
+    ///Fires `event` every time the frame at `frame` is entered
+    pub fn with_event(mut self, frame: usize, event: E) -> Self {
+        self.events.push((frame, event));
+        self
+    }
+
+    pub fn update(&mut self, delta_time: f32) -> Vec<E> {
+        let mut fired = Vec::new();
+        if self.frames.is_empty() || self.finished {
+            return fired;
+        }
+
+        if !self.started {
+            self.started = true;
+            self.fire(self.current, &mut fired);
+        }
+
+        self.elapsed += delta_time;
+        if let Some(cycle) = self.cycle_duration() {
+            //A whole cycle ends on the frame it started from, so a long delta
+            //only needs to step through what is left of the last one
+            self.elapsed %= cycle;
+        }
+
+        loop {
+            let duration = self.frames[self.current].duration.max(MIN_FRAME_DURATION);
+            if self.elapsed < duration {
+                break;
+            }
+
+            match self.next_frame() {
+                Some(next) => {
+                    self.elapsed -= duration;
+                    self.current = next;
+                    self.fire(next, &mut fired);
+                }
+                None => {
+                    self.elapsed = 0.0;
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        fired
+    }
+
+    pub fn uv_rect(&self) -> [f32; 4] {
+        self.frames
+            .get(self.current)
+            .map_or([0.0, 0.0, 1.0, 1.0], |frame| frame.uv_rect)
+    }
+
+    ///Index of the current frame, counting from the first frame of the
+    ///animation
+    pub fn frame(&self) -> usize {
+        self.current
+    }
+
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    ///Returns true once an animation played `Once` is done with its last
+    ///frame. Looping animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    ///Starts over from the first frame, firing its events again on the next
+    ///update
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.elapsed = 0.0;
+        self.forward = true;
+        self.started = false;
+        self.finished = false;
+    }
+
+    fn next_frame(&mut self) -> Option<usize> {
+        let last = self.frames.len() - 1;
+
+        match self.mode {
+            PlaybackMode::Loop => Some(if self.current == last {
+                0
+            } else {
+                self.current + 1
+            }),
+            PlaybackMode::Once => (self.current < last).then_some(self.current + 1),
+            PlaybackMode::PingPong if last == 0 => Some(0),
+            PlaybackMode::PingPong => {
+                if self.current == last {
+                    self.forward = false;
+                } else if self.current == 0 {
+                    self.forward = true;
+                }
+
+                Some(if self.forward {
+                    self.current + 1
+                } else {
+                    self.current - 1
+                })
+            }
+        }
+    }
+
+    ///Time it takes a repeating animation to get back to the same frame,
+    ///going the same direction
+    fn cycle_duration(&self) -> Option<f32> {
+        let durations = self
+            .frames
+            .iter()
+            .map(|frame| frame.duration.max(MIN_FRAME_DURATION));
+
+        match self.mode {
+            PlaybackMode::Once => None,
+            PlaybackMode::Loop => Some(durations.sum()),
+            PlaybackMode::PingPong => {
+                //Every frame but the first and last is shown in both directions
+                let inner = self.frames.len().saturating_sub(2);
+                let repeated: f32 = durations.clone().skip(1).take(inner).sum();
+
+                Some(durations.sum::<f32>() + repeated)
+            }
+        }
+    }
+
+    fn fire(&self, frame: usize, fired: &mut Vec<E>) {
+        fired.extend(
+            self.events
+                .iter()
+                .filter(|(event_frame, _)| *event_frame == frame)
+                .map(|(_, event)| event.clone()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(mode: PlaybackMode) -> SpriteAnimation {
+        let frames = (0..3)
+            .map(|frame| AnimationFrame {
+                uv_rect: [frame as f32, 0.0, 1.0, 1.0],
+                duration: 0.25,
+            })
+            .collect();
+
+        SpriteAnimation::new(frames)
+            .with_mode(mode)
+            .with_event(0, "start")
+            .with_event(2, "end")
+    }
+
+    fn frames(animation: &mut SpriteAnimation, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map(|_| {
+                animation.update(0.25);
+                animation.frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_starts_over_after_the_last_frame() {
+        let mut animation = animation(PlaybackMode::Loop);
+
+        assert_eq!(frames(&mut animation, 7), [1, 2, 0, 1, 2, 0, 1]);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn ping_pong_plays_back_and_forth() {
+        let mut animation = animation(PlaybackMode::PingPong);
+
+        assert_eq!(frames(&mut animation, 7), [1, 2, 1, 0, 1, 2, 1]);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let mut animation = animation(PlaybackMode::Once);
+
+        assert_eq!(frames(&mut animation, 2), [1, 2]);
+        assert!(!animation.is_finished());
+        assert_eq!(frames(&mut animation, 2), [2, 2]);
+        assert!(animation.is_finished());
+        assert_eq!(animation.uv_rect(), [2.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn events_fire_when_their_frame_is_entered() {
+        let mut animation = animation(PlaybackMode::Loop);
+
+        assert_eq!(animation.update(0.0), ["start"]);
+        assert!(animation.update(0.25).is_empty());
+        assert_eq!(animation.update(0.25), ["end"]);
+        assert_eq!(animation.update(0.25), ["start"]);
+        assert_eq!(animation.update(0.5), ["end"]);
+    }
+
+    #[test]
+    fn events_fire_again_after_a_reset() {
+        let mut animation = animation(PlaybackMode::Once);
+        animation.update(1.0);
+        assert!(animation.is_finished());
+
+        animation.reset();
+        assert_eq!(animation.update(0.0), ["start"]);
+        assert_eq!(animation.frame(), 0);
+    }
+
+    #[test]
+    fn long_deltas_skip_whole_cycles() {
+        let mut looping = animation(PlaybackMode::Loop);
+        assert_eq!(looping.update(1000.25), ["start", "end"]);
+        assert_eq!(looping.frame(), 2);
+
+        let mut ping_pong = animation(PlaybackMode::PingPong);
+        assert_eq!(ping_pong.update(1000.25), ["start"]);
+        assert_eq!(ping_pong.frame(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Weak,
+};
+
+use log::info;
+
+use crate::engine::graphics::{mesh::Mesh, shader::Shader, texture::Texture};
+
+use super::{
+    handle::{AssetId, Handle},
+    loader::{Loaded, Loader},
+    watcher::{self, FileWatcher},
+    AssetSource,
+};
+
+struct Entry<T> {
+    asset: Option<T>,
+    path: Option<PathBuf>,
+    handle: Weak<AssetId>,
+}
+
+///Storage for every loaded asset of type `T`.
+///Assets loaded from a file are also indexed by their path, so loading the
+///same path twice returns the same handle.
+pub struct Assets<T> {
+    entries: HashMap<AssetId, Entry<T>>,
+    paths: HashMap<PathBuf, AssetId>,
+}
+
+impl<T> Assets<T> {
+    fn new() -> Self {
+        Assets {
+            entries: HashMap::new(),
+            paths: HashMap::new(),
+        }
+    }
+
+    ///Returns the asset behind `handle`, or `None` while it is still loading
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.entries
+            .get(&handle.id())
+            .and_then(|entry| entry.asset.as_ref())
+    }
+
+    pub fn is_loaded(&self, handle: &Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    ///Returns a handle to the asset loaded from `path`, if it is still alive
+    pub fn find(&self, path: &Path) -> Option<Handle<T>> {
+        let id = self.paths.get(path)?;
+        self.entries
+            .get(id)
+            .and_then(|entry| Handle::upgrade(&entry.handle))
+    }
+
+    fn insert(&mut self, handle: &Handle<T>, asset: Option<T>, path: Option<&Path>) {
+        if let Some(path) = path {
+            self.paths.insert(path.to_path_buf(), handle.id());
+        }
+
+        self.entries.insert(
+            handle.id(),
+            Entry {
+                asset,
+                path: path.map(Path::to_path_buf),
+                handle: handle.downgrade(),
+            },
+        );
+    }
+
+    ///Swaps the asset behind `id`, every existing handle sees the new one.
+    ///Returns `false` if the asset was already freed.
+    pub(crate) fn replace(&mut self, id: AssetId, asset: T) -> bool {
+        match self.entries.get_mut(&id) {
+            Some(entry) => {
+                entry.asset = Some(asset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///Drops every asset that has no handles left and returns their ids
+    fn collect_garbage(&mut self) -> Vec<AssetId> {
+        let unused: Vec<AssetId> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.handle.strong_count() == 0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &unused {
+            if let Some(path) = self.remove(*id) {
+                info!("Asset freed: {:?}", path);
+            }
+        }
+
+        unused
+    }
+
+    ///Drops the entry behind `id` along with its path mapping, and returns
+    ///the path it was loaded from
+    fn remove(&mut self, id: AssetId) -> Option<PathBuf> {
+        let path = self.entries.remove(&id)?.path?;
+
+        //The path may already point at a newer load of the same file
+        if self.paths.get(&path) == Some(&id) {
+            self.paths.remove(&path);
+        }
+
+        Some(path)
+    }
+}
+
+///Central owner of the textures, shaders and meshes used by `Graphics`.
+///Loads hand out typed `Handle`s, loading a path that is already loaded
+///returns the existing handle, and assets are freed (along with their GPU
+///resources) once the last handle to them is dropped.
+pub struct AssetServer {
+    next_id: u64,
+    pub textures: Assets<Texture>,
+    pub shaders: Assets<Shader>,
+    pub meshes: Assets<Mesh>,
+    texture_watcher: FileWatcher<AssetId>,
+    shader_watcher: FileWatcher<AssetId>,
+    loader: Loader,
+}
+
+impl AssetServer {
+    pub fn new() -> Self {
+        AssetServer {
+            next_id: 0,
+            textures: Assets::new(),
+            shaders: Assets::new(),
+            meshes: Assets::new(),
+            texture_watcher: FileWatcher::new(watcher::DEFAULT_POLL_INTERVAL),
+            shader_watcher: FileWatcher::new(watcher::DEFAULT_POLL_INTERVAL),
+            loader: Loader::new(),
+        }
+    }
+
+    fn next_handle<T>(&mut self) -> Handle<T> {
+        let handle = Handle::new(AssetId(self.next_id));
+        self.next_id += 1;
+        handle
+    }
+
+    ///Stores `texture` and starts watching `path` for changes, if any
+    pub fn add_texture(&mut self, texture: Texture, path: Option<&Path>) -> Handle<Texture> {
+        let handle = self.next_handle();
+        self.textures.insert(&handle, Some(texture), path);
+
+        if let Some(path) = path {
+            self.texture_watcher.watch(handle.id(), path);
+        }
+
+        handle
+    }
+
+    ///Stores `shader` and starts watching its path for changes
+    pub fn add_shader(&mut self, shader: Shader) -> Handle<Shader> {
+        let handle = self.next_handle();
+        let path = shader.path.clone();
+        self.shaders.insert(&handle, Some(shader), Some(&path));
+        self.shader_watcher.watch(handle.id(), &path);
+
+        handle
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) -> Handle<Mesh> {
+        let handle = self.next_handle();
+        self.meshes.insert(&handle, Some(mesh), None);
+
+        handle
+    }
+
+    ///Starts decoding the texture read from `source` on a worker thread and returns
+    ///its handle right away. The texture becomes available through
+    ///`textures.get` once `Graphics` uploads it.
+    pub fn load_texture_async(&mut self, source: AssetSource) -> Handle<Texture> {
+        let path = source.path();
+        if let Some(handle) = self.textures.find(&path) {
+            return handle;
+        }
+
+        let handle = self.next_handle();
+        self.textures.insert(&handle, None, Some(&path));
+        self.loader.load_texture(handle.id(), source);
+
+        handle
+    }
+
+    ///Starts reading the shader from `source` on a worker thread and returns its
+    ///handle right away
+    pub fn load_shader_async(&mut self, source: AssetSource) -> Handle<Shader> {
+        let path = source.path();
+        if let Some(handle) = self.shaders.find(&path) {
+            return handle;
+        }
+
+        let handle = self.next_handle();
+        self.shaders.insert(&handle, None, Some(&path));
+        self.loader.load_shader(handle.id(), source);
+
+        handle
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_loading()
+    }
+
+    ///Fraction of the background loads that finished, from `0.0` to `1.0`.
+    ///It is `1.0` when nothing is loading, and starts over with the next
+    ///batch of loads once every pending one is done.
+    pub fn progress(&self) -> f32 {
+        self.loader.progress()
+    }
+
+    pub(crate) fn finished_loads(&mut self) -> Vec<Loaded> {
+        self.loader.finished()
+    }
+
+    ///Stores a texture that finished loading in the background and starts
+    ///watching its file
+    pub(crate) fn finish_texture(&mut self, id: AssetId, path: &Path, texture: Texture) {
+        if self.textures.replace(id, texture) {
+            self.texture_watcher.watch(id, path);
+        }
+    }
+
+    pub(crate) fn finish_shader(&mut self, id: AssetId, shader: Shader) {
+        let path = shader.path.clone();
+
+        if self.shaders.replace(id, shader) {
+            self.shader_watcher.watch(id, &path);
+        }
+    }
+
+    ///Forgets a texture whose background load failed, so its handles never
+    ///resolve and loading the same path again starts a new load
+    pub(crate) fn fail_texture(&mut self, id: AssetId) {
+        self.textures.remove(id);
+    }
+
+    pub(crate) fn fail_shader(&mut self, id: AssetId) {
+        self.shaders.remove(id);
+    }
+
+    pub(crate) fn changed_textures(&mut self) -> Vec<(AssetId, PathBuf)> {
+        self.texture_watcher.changed()
+    }
+
+    pub(crate) fn changed_shaders(&mut self) -> Vec<(AssetId, PathBuf)> {
+        self.shader_watcher.changed()
+    }
+
+    ///Frees every asset that is no longer referenced by a handle
+    pub fn collect_garbage(&mut self) {
+        for id in self.textures.collect_garbage() {
+            self.texture_watcher.unwatch(id);
+        }
+
+        for id in self.shaders.collect_garbage() {
+            self.shader_watcher.unwatch(id);
+        }
+
+        self.meshes.collect_garbage();
+    }
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        AssetServer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_entries_are_not_found_by_path() {
+        let mut assets = Assets::<u32>::new();
+        let path = Path::new("missing.png");
+        let handle = Handle::new(AssetId(0));
+        assets.insert(&handle, None, Some(path));
+
+        assert_eq!(assets.find(path), Some(handle.clone()));
+        assert_eq!(assets.remove(handle.id()), Some(path.to_path_buf()));
+        assert_eq!(assets.find(path), None);
+        assert!(!assets.is_loaded(&handle));
+    }
+
+    #[test]
+    fn removing_an_old_entry_keeps_the_newer_path() {
+        let mut assets = Assets::<u32>::new();
+        let path = Path::new("texture.png");
+        let old = Handle::new(AssetId(0));
+        let new = Handle::new(AssetId(1));
+        assets.insert(&old, Some(1), Some(path));
+        assets.insert(&new, Some(2), Some(path));
+
+        assets.remove(old.id());
+        assert_eq!(assets.find(path), Some(new));
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::{Arc, Weak},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(pub(crate) u64);
+
+///Typed, reference-counted reference to an asset owned by the `AssetServer`.
+///Cloning a handle is cheap, and the asset is freed once every handle to it
+///has been dropped.
+pub struct Handle<T> {
+    id: Arc<AssetId>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: AssetId) -> Self {
+        Handle {
+            id: Arc::new(id),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        *self.id
+    }
+
+    ///Number of handles to the asset, including this one
+    pub(crate) fn count(&self) -> usize {
+        Arc::strong_count(&self.id)
+    }
+
+    pub(crate) fn downgrade(&self) -> Weak<AssetId> {
+        Arc::downgrade(&self.id)
+    }
+
+    pub(crate) fn upgrade(weak: &Weak<AssetId>) -> Option<Self> {
+        weak.upgrade().map(|id| Handle {
+            id,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            id: Arc::clone(&self.id),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.id().0)
+    }
+}
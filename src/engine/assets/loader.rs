@@ -0,0 +1,154 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use image::RgbaImage;
+use log::info;
+
+use crate::engine::{
+    error::GenesisError,
+    graphics::{shader::Shader, texture::Texture},
+};
+
+use super::{handle::AssetId, AssetSource};
+
+///Upper bound for the number of loading threads, decoding is mostly bound by
+///disk and memory bandwidth past that point
+const MAX_WORKERS: usize = 4;
+
+enum Job {
+    Texture(AssetId, AssetSource),
+    Shader(AssetId, AssetSource),
+}
+
+///Result of a background load. Textures are only decoded, the upload to the
+///GPU happens on the main thread.
+pub(crate) enum Loaded {
+    Texture(AssetId, PathBuf, Result<RgbaImage, GenesisError>),
+    Shader(AssetId, PathBuf, Result<Shader, GenesisError>),
+}
+
+///Pool of worker threads that read and decode assets in the background.
+///Threads are only spawned once the first load is requested, and keep track
+///of how much of the requested work is done so far.
+pub(crate) struct Loader {
+    jobs: Option<Sender<Job>>,
+    results_sender: Sender<Loaded>,
+    results: Receiver<Loaded>,
+    requested: usize,
+    completed: usize,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        let (results_sender, results) = mpsc::channel();
+
+        Loader {
+            jobs: None,
+            results_sender,
+            results,
+            requested: 0,
+            completed: 0,
+        }
+    }
+
+    pub fn load_texture(&mut self, id: AssetId, source: AssetSource) {
+        self.request(Job::Texture(id, source));
+    }
+
+    pub fn load_shader(&mut self, id: AssetId, source: AssetSource) {
+        self.request(Job::Shader(id, source));
+    }
+
+    ///Returns every load that finished since the last call
+    pub fn finished(&mut self) -> Vec<Loaded> {
+        let finished: Vec<Loaded> = self.results.try_iter().collect();
+        self.completed += finished.len();
+
+        if self.completed == self.requested {
+            self.requested = 0;
+            self.completed = 0;
+        }
+
+        finished
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.requested > 0
+    }
+
+    ///Fraction of the loads requested since the loader was last idle that
+    ///have finished, `1.0` when nothing is loading
+    pub fn progress(&self) -> f32 {
+        if self.requested == 0 {
+            return 1.0;
+        }
+
+        self.completed as f32 / self.requested as f32
+    }
+
+    fn request(&mut self, job: Job) {
+        let jobs = self
+            .jobs
+            .get_or_insert_with(|| spawn_workers(&self.results_sender));
+
+        if jobs.send(job).is_ok() {
+            self.requested += 1;
+        }
+    }
+}
+
+fn spawn_workers(results: &Sender<Loaded>) -> Sender<Job> {
+    let (jobs_sender, jobs) = mpsc::channel::<Job>();
+    let jobs = Arc::new(Mutex::new(jobs));
+    let workers = thread::available_parallelism()
+        .map(|workers| workers.get())
+        .unwrap_or(1)
+        .min(MAX_WORKERS);
+
+    for worker in 0..workers {
+        let jobs = Arc::clone(&jobs);
+        let results = results.clone();
+
+        thread::Builder::new()
+            .name(format!("genesis-loader-{}", worker))
+            .spawn(move || loop {
+                let job = match jobs.lock() {
+                    Ok(jobs) => jobs.recv(),
+                    Err(_) => return,
+                };
+
+                let loaded = match job {
+                    Ok(Job::Texture(id, source)) => {
+                        let path = source.path();
+                        let image = source
+                            .read()
+                            .and_then(|bytes| Texture::decode_bytes(&bytes, &path));
+                        Loaded::Texture(id, path, image)
+                    }
+                    Ok(Job::Shader(id, source)) => {
+                        let path = source.path();
+                        let shader = source
+                            .read()
+                            .and_then(|bytes| Shader::from_bytes(path.clone(), bytes));
+                        Loaded::Shader(id, path, shader)
+                    }
+                    Err(_) => return,
+                };
+
+                if results.send(loaded).is_err() {
+                    return;
+                }
+            })
+            .expect("Unable to spawn asset loading thread");
+    }
+
+    info!("Asset loader started with {} threads", workers);
+
+    jobs_sender
+}
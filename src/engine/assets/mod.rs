@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    panic::Location,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::engine::error::GenesisError;
+
+use self::pack::{pack_name, AssetPack};
+
+pub mod asset_server;
+pub mod handle;
+pub(crate) mod loader;
+pub mod pack;
+mod watcher;
+
+///When set, this environment variable overrides the configured asset root
+pub const ASSET_ROOT_ENV: &str = "GENESIS_ASSET_ROOT";
+
+///Decides where the relative paths given to `load_texture` and `load_shader`
+///are read from. A path is resolved in this order:
+///1. Absolute paths are used as they are
+///2. If `GENESIS_ASSET_ROOT` is set, the path is joined onto it
+///3. If a root was configured, the path is joined onto it. Relative roots are
+///relative to the directory of the executable
+///4. Otherwise the first existing file out of the directory of the
+///executable, the current directory and the directory of the source file
+///that made the call is used. When none exists the executable directory is
+///assumed, so errors point at where shipped builds look for assets
+///
+///Assets embedded into the executable with `embed` take precedence over
+///everything else. When an `AssetPack` is set, paths found in it are read
+///from the pack instead, and everything else still comes from the filesystem.
+#[derive(Debug, Clone, Default)]
+pub struct AssetRoot {
+    root: Option<PathBuf>,
+    pack: Option<Arc<AssetPack>>,
+    embedded: HashMap<String, &'static [u8]>,
+}
+
+impl AssetRoot {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        AssetRoot {
+            root: Some(root.as_ref().to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    ///Changes the root, keeping the pack and embedded assets
+    pub fn set_root<P: AsRef<Path>>(&mut self, root: P) {
+        self.root = Some(root.as_ref().to_path_buf());
+    }
+
+    ///Registers `bytes` as the contents of the virtual path `file_path`, so
+    ///loading that path reads them instead of touching the filesystem. Meant
+    ///to be used with `include_bytes!` to ship a single executable.
+    pub fn embed(&mut self, file_path: &str, bytes: &'static [u8]) {
+        self.embedded.insert(virtual_name(file_path), bytes);
+    }
+
+    pub fn set_pack(&mut self, pack: AssetPack) {
+        self.pack = Some(Arc::new(pack));
+    }
+
+    pub fn pack(&self) -> Option<&AssetPack> {
+        self.pack.as_deref()
+    }
+
+    ///Returns where the asset at `file_path` is read from: the embedded
+    ///assets or the asset pack if either contains the path, otherwise the
+    ///file `resolve` points at
+    #[track_caller]
+    pub fn source(&self, file_path: &str) -> AssetSource {
+        let name = virtual_name(file_path);
+        if let Some(bytes) = self.embedded.get(&name) {
+            return AssetSource::Embedded(name, bytes);
+        }
+
+        let packed = self.pack.as_ref().and_then(|pack| {
+            pack_name(Path::new(file_path))
+                .filter(|name| pack.contains(name))
+                .map(|name| AssetSource::Packed(Arc::clone(pack), name))
+        });
+
+        //Not `unwrap_or_else`, `resolve` can't see the caller from inside a closure
+        match packed {
+            Some(packed) => packed,
+            None => AssetSource::File(self.resolve(file_path)),
+        }
+    }
+
+    ///Returns the root in use, taking the environment override into account.
+    ///`None` means paths are looked up in the default locations.
+    pub fn root(&self) -> Option<PathBuf> {
+        if let Some(root) = env::var_os(ASSET_ROOT_ENV) {
+            return Some(PathBuf::from(root));
+        }
+
+        self.root.as_ref().map(|root| executable_dir().join(root))
+    }
+
+    #[track_caller]
+    pub fn resolve(&self, file_path: &str) -> PathBuf {
+        let path = Path::new(file_path);
+
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+
+        if let Some(root) = self.root() {
+            return root.join(path);
+        }
+
+        let executable_path = executable_dir().join(path);
+        let mut candidates = vec![executable_path.clone()];
+
+        if let Ok(current_dir) = env::current_dir() {
+            candidates.push(current_dir.join(path));
+
+            let caller_dir = Path::new(Location::caller().file())
+                .parent()
+                .unwrap_or(Path::new(""));
+            candidates.push(current_dir.join(caller_dir).join(path));
+        }
+
+        candidates
+            .into_iter()
+            .find(|candidate| candidate.exists())
+            .unwrap_or(executable_path)
+    }
+}
+
+///Where the bytes of an asset are read from
+#[derive(Debug, Clone)]
+pub enum AssetSource {
+    File(PathBuf),
+    Packed(Arc<AssetPack>, String),
+    Embedded(String, &'static [u8]),
+}
+
+impl AssetSource {
+    ///Path identifying the asset in errors and logs. Packed assets are
+    ///shown as if the pack was a directory.
+    pub fn path(&self) -> PathBuf {
+        match self {
+            AssetSource::File(path) => path.clone(),
+            AssetSource::Packed(pack, name) => pack.path().join(name),
+            AssetSource::Embedded(name, _) => virtual_path(name),
+        }
+    }
+
+    pub fn read(&self) -> Result<Vec<u8>, GenesisError> {
+        match self {
+            AssetSource::File(path) => fs::read(path).map_err(|source| GenesisError::Io {
+                path: path.clone(),
+                source,
+            }),
+            AssetSource::Packed(pack, name) => pack.read(name),
+            AssetSource::Embedded(_, bytes) => Ok(bytes.to_vec()),
+        }
+    }
+}
+
+///Path of an asset that doesn't come from a file, like embedded assets or
+///the ones created from bytes at runtime
+pub fn virtual_path(name: &str) -> PathBuf {
+    Path::new("embedded:").join(name)
+}
+
+fn virtual_name(file_path: &str) -> String {
+    pack_name(Path::new(file_path)).unwrap_or_else(|| file_path.to_string())
+}
+
+fn executable_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
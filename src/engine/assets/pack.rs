@@ -0,0 +1,515 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use log::info;
+
+use crate::engine::error::GenesisError;
+
+///File name the engine looks for in the asset root when no pack is configured
+pub const DEFAULT_ASSET_PACK: &str = "assets.gpak";
+
+const MAGIC: &[u8; 4] = b"GPAK";
+const VERSION: u32 = 1;
+
+///Size of an index entry without its name: name length, compression,
+///offset, stored size and size
+const ENTRY_SIZE: u64 = 4 + 1 + 8 * 3;
+
+///Deflate can't expand data by more than this, so entries claiming a larger
+///ratio are corrupt
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+///How the files of a pack are stored. Compressed files that don't end up
+///smaller are stored as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Deflate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PackEntry {
+    offset: u64,
+    stored_size: u64,
+    size: u64,
+    compression: Compression,
+}
+
+///Archive bundling the assets of a game into a single file.
+///The file starts with a `GPAK` header and an index of every packed file,
+///keyed by its path relative to the packed directory with `/` separators,
+///followed by the contents of the files. Only the index is kept in memory,
+///files are read from disk when they are loaded.
+///Packs are created with `AssetPack::create` or the `genesis_pack` binary.
+#[derive(Debug)]
+pub struct AssetPack {
+    path: PathBuf,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl AssetPack {
+    ///Opens the pack at `path` and reads its index
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GenesisError> {
+        let path = path.as_ref();
+        let io_error = |source| GenesisError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let mut file = File::open(path).map_err(io_error)?;
+        let entries = read_index(&mut file).map_err(io_error)?;
+
+        info!("Asset pack opened: {:?} ({} files)", path, entries.len());
+
+        Ok(AssetPack {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    ///Names of every packed file
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    ///Reads and decompresses the packed file `name`
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, GenesisError> {
+        let io_error = |source| GenesisError::Io {
+            path: self.path.join(name),
+            source,
+        };
+
+        let entry = self.entries.get(name).ok_or_else(|| {
+            io_error(io::Error::new(
+                io::ErrorKind::NotFound,
+                "file not found in asset pack",
+            ))
+        })?;
+
+        let mut file = File::open(&self.path).map_err(io_error)?;
+        file.seek(SeekFrom::Start(entry.offset)).map_err(io_error)?;
+        let stored = file.take(entry.stored_size);
+
+        let mut contents = Vec::with_capacity(entry.size as usize);
+        match entry.compression {
+            Compression::None => stored.take(entry.size).read_to_end(&mut contents),
+            Compression::Deflate => DeflateDecoder::new(stored)
+                .take(entry.size)
+                .read_to_end(&mut contents),
+        }
+        .map_err(io_error)?;
+
+        if contents.len() as u64 != entry.size {
+            return Err(io_error(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "packed file is truncated",
+            )));
+        }
+
+        Ok(contents)
+    }
+
+    ///Packs every file under `directory` into a new pack at `output` and
+    ///returns how many files were packed
+    pub fn create<P: AsRef<Path>, Q: AsRef<Path>>(
+        directory: P,
+        output: Q,
+        compression: Compression,
+    ) -> Result<usize, GenesisError> {
+        let directory = directory.as_ref();
+        let output = output.as_ref();
+
+        let mut files = Vec::new();
+        collect_files(directory, &mut files)?;
+        files.retain(|file| file.as_path() != output);
+        files.sort();
+
+        let mut packed = Vec::new();
+        for file in &files {
+            //Collected files are always inside the packed directory, so only
+            //names that aren't valid UTF-8 can't be packed
+            let name = file
+                .strip_prefix(directory)
+                .ok()
+                .and_then(pack_name)
+                .ok_or_else(|| GenesisError::Io {
+                    path: file.clone(),
+                    source: io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "file name is not valid UTF-8",
+                    ),
+                })?;
+            let contents = fs::read(file).map_err(|source| GenesisError::Io {
+                path: file.clone(),
+                source,
+            })?;
+            let (stored, compression) = compress(&contents, compression);
+
+            packed.push((name, contents.len() as u64, stored, compression));
+        }
+
+        write_pack(output, &packed).map_err(|source| GenesisError::Io {
+            path: output.to_path_buf(),
+            source,
+        })?;
+
+        info!("Asset pack created: {:?} ({} files)", output, packed.len());
+
+        Ok(packed.len())
+    }
+}
+
+///Turns a relative path into the name it is packed under, or `None` if it
+///can't be inside a pack
+pub fn pack_name(path: &Path) -> Option<String> {
+    let mut names = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => names.push(name.to_str()?),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(names.join("/"))
+}
+
+///Reads the index, checking every length and offset in it against the size
+///of the file so a corrupt pack can't make it allocate more than the file
+///could hold
+fn read_index(file: &mut File) -> io::Result<HashMap<String, PackEntry>> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+    let file_len = file.metadata()?.len();
+
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a genesis asset pack"));
+    }
+
+    if read_u32(file)? != VERSION {
+        return Err(invalid("unsupported asset pack version"));
+    }
+
+    let count = read_u32(file)?;
+    if count as u64 * ENTRY_SIZE > file_len {
+        return Err(invalid("asset pack index is larger than the file"));
+    }
+    let mut entries = HashMap::with_capacity(count as usize);
+
+    for _ in 0..count {
+        let name_len = read_u32(file)?;
+        if name_len as u64 > file_len.saturating_sub(file.stream_position()?) {
+            return Err(invalid("file name runs past the end of the asset pack"));
+        }
+        let mut name = vec![0; name_len as usize];
+        file.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| invalid("invalid file name"))?;
+
+        let mut compression = [0; 1];
+        file.read_exact(&mut compression)?;
+        let compression =
+            Compression::from_id(compression[0]).ok_or_else(|| invalid("unknown compression"))?;
+
+        let entry = PackEntry {
+            offset: read_u64(file)?,
+            stored_size: read_u64(file)?,
+            size: read_u64(file)?,
+            compression,
+        };
+
+        match entry.offset.checked_add(entry.stored_size) {
+            Some(end) if end <= file_len => {}
+            _ => return Err(invalid("packed file runs past the end of the asset pack")),
+        }
+
+        let max_size = match compression {
+            Compression::None => entry.stored_size,
+            Compression::Deflate => entry.stored_size.saturating_mul(MAX_DEFLATE_RATIO),
+        };
+        if entry.size > max_size {
+            return Err(invalid(
+                "packed file is larger than its stored contents allow",
+            ));
+        }
+
+        entries.insert(name, entry);
+    }
+
+    Ok(entries)
+}
+
+fn write_pack(output: &Path, packed: &[(String, u64, Vec<u8>, Compression)]) -> io::Result<()> {
+    let index_size: usize = packed
+        .iter()
+        .map(|(name, ..)| ENTRY_SIZE as usize + name.len())
+        .sum();
+    let mut offset = (MAGIC.len() + 4 + 4 + index_size) as u64;
+
+    let mut pack = Vec::new();
+    pack.extend_from_slice(MAGIC);
+    pack.extend_from_slice(&VERSION.to_le_bytes());
+    pack.extend_from_slice(&(packed.len() as u32).to_le_bytes());
+
+    for (name, size, stored, compression) in packed {
+        pack.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        pack.extend_from_slice(name.as_bytes());
+        pack.push(compression.id());
+        pack.extend_from_slice(&offset.to_le_bytes());
+        pack.extend_from_slice(&(stored.len() as u64).to_le_bytes());
+        pack.extend_from_slice(&size.to_le_bytes());
+
+        offset += stored.len() as u64;
+    }
+
+    for (_, _, stored, _) in packed {
+        pack.extend_from_slice(stored);
+    }
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(output, pack)
+}
+
+fn compress(contents: &[u8], compression: Compression) -> (Vec<u8>, Compression) {
+    if compression == Compression::Deflate {
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+
+        if let Ok(compressed) = encoder.write_all(contents).and_then(|_| encoder.finish()) {
+            if compressed.len() < contents.len() {
+                return (compressed, Compression::Deflate);
+            }
+        }
+    }
+
+    (contents.to_vec(), Compression::None)
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), GenesisError> {
+    let io_error = |source| GenesisError::Io {
+        path: directory.to_path_buf(),
+        source,
+    };
+
+    for entry in fs::read_dir(directory).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_u32(file: &mut File) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    file.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(file: &mut File) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Scratch directory removed again when the test ends, even if it fails
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("genesis_pack_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl std::ops::Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn header(count: u32) -> Vec<u8> {
+        let mut pack = MAGIC.to_vec();
+        pack.extend_from_slice(&VERSION.to_le_bytes());
+        pack.extend_from_slice(&count.to_le_bytes());
+        pack
+    }
+
+    fn assert_invalid(pack: &[u8], name: &str) {
+        let dir = TempDir::new(name);
+        let path = dir.join("corrupt.gpak");
+        fs::write(&path, pack).unwrap();
+
+        match AssetPack::open(&path) {
+            Err(GenesisError::Io { source, .. }) => {
+                assert_eq!(source.kind(), io::ErrorKind::InvalidData)
+            }
+            other => panic!("expected invalid data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trips_files() {
+        let dir = TempDir::new("round_trip");
+        let compressible = vec![7; 4096];
+        fs::create_dir_all(dir.join("textures")).unwrap();
+        fs::write(dir.join("a.txt"), b"hello").unwrap();
+        fs::write(dir.join("textures/b.bin"), &compressible).unwrap();
+        fs::write(dir.join("empty"), b"").unwrap();
+
+        let output = dir.join("assets.gpak");
+        assert_eq!(
+            AssetPack::create(&*dir, &output, Compression::Deflate).unwrap(),
+            3
+        );
+
+        let pack = AssetPack::open(&output).unwrap();
+        let mut names: Vec<&str> = pack.names().collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "empty", "textures/b.bin"]);
+        assert_eq!(pack.read("a.txt").unwrap(), b"hello");
+        assert_eq!(pack.read("textures/b.bin").unwrap(), compressible);
+        assert!(pack.read("empty").unwrap().is_empty());
+        assert!(pack.entries["textures/b.bin"].stored_size < compressible.len() as u64);
+        assert!(pack.read("missing").is_err());
+    }
+
+    #[test]
+    fn does_not_pack_its_own_output() {
+        let dir = TempDir::new("own_output");
+        fs::write(dir.join("a.txt"), b"hello").unwrap();
+
+        let output = dir.join("assets.gpak");
+        AssetPack::create(&*dir, &output, Compression::None).unwrap();
+        assert_eq!(
+            AssetPack::create(&*dir, &output, Compression::None).unwrap(),
+            1
+        );
+
+        let pack = AssetPack::open(&output).unwrap();
+        assert!(!pack.contains("assets.gpak"));
+        assert_eq!(pack.read("a.txt").unwrap(), b"hello");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_file_names_that_are_not_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = TempDir::new("not_utf8");
+        let file = dir.join(OsStr::from_bytes(b"bad\xff.png"));
+        fs::write(&file, b"").unwrap();
+
+        match AssetPack::create(&*dir, dir.join("assets.gpak"), Compression::None) {
+            Err(GenesisError::Io { path, source }) => {
+                assert_eq!(path, file);
+                assert_eq!(source.kind(), io::ErrorKind::InvalidData);
+            }
+            other => panic!("expected invalid data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_a_count_larger_than_the_file() {
+        assert_invalid(&header(u32::MAX), "count");
+    }
+
+    #[test]
+    fn rejects_a_name_longer_than_the_file() {
+        let mut pack = header(1);
+        pack.extend_from_slice(&u32::MAX.to_le_bytes());
+        pack.extend_from_slice(&[0; ENTRY_SIZE as usize]);
+
+        assert_invalid(&pack, "name");
+    }
+
+    #[test]
+    fn rejects_contents_past_the_end_of_the_file() {
+        let mut pack = header(1);
+        pack.extend_from_slice(&1u32.to_le_bytes());
+        pack.push(b'a');
+        pack.push(Compression::None.id());
+        pack.extend_from_slice(&u64::MAX.to_le_bytes());
+        pack.extend_from_slice(&u64::MAX.to_le_bytes());
+        pack.extend_from_slice(&u64::MAX.to_le_bytes());
+
+        assert_invalid(&pack, "offset");
+    }
+
+    #[test]
+    fn rejects_a_size_larger_than_the_stored_contents() {
+        let mut pack = header(1);
+        pack.extend_from_slice(&1u32.to_le_bytes());
+        pack.push(b'a');
+        pack.push(Compression::Deflate.id());
+        let offset = (pack.len() + 8 * 3) as u64;
+        pack.extend_from_slice(&offset.to_le_bytes());
+        pack.extend_from_slice(&1u64.to_le_bytes());
+        pack.extend_from_slice(&u64::MAX.to_le_bytes());
+        pack.push(0);
+
+        assert_invalid(&pack, "size");
+    }
+}
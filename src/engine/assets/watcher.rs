@@ -0,0 +1,63 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+///Keeps track of the modification time of a set of files, each one
+///identified by a `key`. Files are polled at most once per `interval`, which
+///keeps the cost of watching low without any platform specific APIs.
+pub struct FileWatcher<K> {
+    files: Vec<(K, PathBuf, Option<SystemTime>)>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+impl<K: Copy + PartialEq> FileWatcher<K> {
+    pub fn new(interval: Duration) -> Self {
+        FileWatcher {
+            files: Vec::new(),
+            interval,
+            last_poll: Instant::now(),
+        }
+    }
+
+    ///Starts watching `path`, replacing the previous path watched for `key`
+    pub fn watch(&mut self, key: K, path: &Path) {
+        self.files.retain(|(watched, _, _)| *watched != key);
+        self.files
+            .push((key, path.to_path_buf(), modified_time(path)));
+    }
+
+    pub fn unwatch(&mut self, key: K) {
+        self.files.retain(|(watched, _, _)| *watched != key);
+    }
+
+    ///Returns the keys and paths of the files that changed since the last
+    ///poll. Returns nothing if called again before `interval` has passed.
+    pub fn changed(&mut self) -> Vec<(K, PathBuf)> {
+        if self.last_poll.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+        for (key, path, last_modified) in &mut self.files {
+            let modified = modified_time(path);
+            if modified.is_some() && modified != *last_modified {
+                *last_modified = modified;
+                changed.push((*key, path.clone()));
+            }
+        }
+
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
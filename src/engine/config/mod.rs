@@ -1,10 +1,18 @@
+use std::path::PathBuf;
+
 ///This struct holds the configurable options of the window
 ///They can be queried and updated
+///`asset_root` is where relative asset paths are resolved from, see `AssetRoot`
+///`asset_pack` is the asset pack to read assets from, see `Engine::with_asset_pack`
+///`sample_count` is the number of MSAA samples per pixel, 1 disables it
 pub struct Config {
     pub title: String,
     pub width: u32,
     pub height: u32,
     pub fullscreen: bool,
+    pub asset_root: Option<PathBuf>,
+    pub asset_pack: Option<PathBuf>,
+    pub sample_count: u32,
 }
 
 impl Config {
@@ -14,6 +22,9 @@ impl Config {
             width,
             height,
             fullscreen,
+            asset_root: None,
+            asset_pack: None,
+            sample_count: 1,
         }
     }
 }
@@ -0,0 +1,114 @@
+use std::{fmt, io, path::PathBuf};
+
+///Errors that can happen while creating the engine or loading its assets.
+///Every fallible `try_*` function returns one of these, while their
+///non-`try` counterparts panic with the same message.
+#[derive(Debug)]
+pub enum GenesisError {
+    EventLoop(winit::error::EventLoopError),
+    Window(winit::error::OsError),
+    CreateSurface(wgpu::CreateSurfaceError),
+    AdapterNotFound,
+    RequestDevice(wgpu::RequestDeviceError),
+    SurfaceUnsupported,
+    Headless,
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    Shader {
+        path: PathBuf,
+        line: Option<u32>,
+        column: Option<u32>,
+        message: String,
+    },
+    Atlas(String),
+}
+
+impl fmt::Display for GenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenesisError::EventLoop(err) => write!(f, "Event loop error: {}", err),
+            GenesisError::Window(err) => write!(f, "Unable to create window: {}", err),
+            GenesisError::CreateSurface(err) => write!(f, "Unable to create surface: {}", err),
+            GenesisError::AdapterNotFound => write!(f, "No compatible graphics adapter found"),
+            GenesisError::RequestDevice(err) => write!(f, "Unable to request device: {}", err),
+            GenesisError::SurfaceUnsupported => {
+                write!(f, "The surface is not supported by the adapter")
+            }
+            GenesisError::Headless => {
+                write!(
+                    f,
+                    "A headless engine has no event loop, use `run_frames` instead"
+                )
+            }
+            GenesisError::Io { path, source } => write!(f, "Unable to read {:?}: {}", path, source),
+            GenesisError::Image { path, source } => {
+                write!(f, "Unable to decode image {:?}: {}", path, source)
+            }
+            GenesisError::Shader {
+                path,
+                line: Some(line),
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "Shader error in {}:{}:{}\n{}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            GenesisError::Shader { path, message, .. } => {
+                write!(f, "Shader error in {}\n{}", path.display(), message)
+            }
+            GenesisError::Atlas(message) => write!(f, "Texture atlas error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GenesisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GenesisError::EventLoop(err) => Some(err),
+            GenesisError::Window(err) => Some(err),
+            GenesisError::CreateSurface(err) => Some(err),
+            GenesisError::RequestDevice(err) => Some(err),
+            GenesisError::Io { source, .. } => Some(source),
+            GenesisError::Image { source, .. } => Some(source),
+            GenesisError::AdapterNotFound
+            | GenesisError::SurfaceUnsupported
+            | GenesisError::Headless
+            | GenesisError::Shader { .. }
+            | GenesisError::Atlas(_) => None,
+        }
+    }
+}
+
+impl From<winit::error::EventLoopError> for GenesisError {
+    fn from(err: winit::error::EventLoopError) -> Self {
+        GenesisError::EventLoop(err)
+    }
+}
+
+impl From<winit::error::OsError> for GenesisError {
+    fn from(err: winit::error::OsError) -> Self {
+        GenesisError::Window(err)
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for GenesisError {
+    fn from(err: wgpu::CreateSurfaceError) -> Self {
+        GenesisError::CreateSurface(err)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for GenesisError {
+    fn from(err: wgpu::RequestDeviceError) -> Self {
+        GenesisError::RequestDevice(err)
+    }
+}
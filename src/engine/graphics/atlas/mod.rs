@@ -0,0 +1,313 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use image::RgbaImage;
+
+use crate::engine::{assets::handle::Handle, error::GenesisError};
+
+use super::texture::Texture;
+
+///Empty pixels left around every packed image, so linear filtering doesn't
+///bleed the neighbouring images into it
+const PADDING: u32 = 1;
+
+///Rectangle of a texture in pixels, with its origin at the top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRegion {
+    ///The region in texture coordinates of a texture of `texture_width` by
+    ///`texture_height` pixels, `[x, y, width, height]` as taken by
+    ///`Sprite::with_uv_rect`
+    pub fn uv_rect(&self, texture_width: u32, texture_height: u32) -> [f32; 4] {
+        [
+            self.x as f32 / texture_width as f32,
+            self.y as f32 / texture_height as f32,
+            self.width as f32 / texture_width as f32,
+            self.height as f32 / texture_height as f32,
+        ]
+    }
+}
+
+///Several images packed into a single texture, so sprites using any of them
+///can be drawn without switching textures. Each image is a named region,
+///named after the path it was loaded from with `Graphics::load_atlas` or
+///the name it was given to `Graphics::create_atlas`.
+pub struct TextureAtlas {
+    pub texture: Handle<Texture>,
+    pub width: u32,
+    pub height: u32,
+    pub(crate) regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).copied()
+    }
+
+    pub fn uv_rect(&self, name: &str) -> Option<[f32; 4]> {
+        self.region(name)
+            .map(|region| region.uv_rect(self.width, self.height))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(String::as_str)
+    }
+
+    ///Treats the region `name` as a grid of frames, see `SpriteSheet`
+    pub fn sprite_sheet(
+        &self,
+        name: &str,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Result<SpriteSheet, GenesisError> {
+        let region = self
+            .region(name)
+            .ok_or_else(|| GenesisError::Atlas(format!("no region named {:?}", name)))?;
+
+        SpriteSheet::new(
+            self.texture.clone(),
+            (self.width, self.height),
+            region,
+            frame_width,
+            frame_height,
+        )
+    }
+}
+
+///A region of a texture split into a grid of equally sized frames, numbered
+///left to right and top to bottom. Leftover pixels on the right and bottom
+///edges that can't hold a whole frame are ignored.
+pub struct SpriteSheet {
+    pub texture: Handle<Texture>,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    texture_size: (u32, u32),
+    region: AtlasRegion,
+}
+
+impl SpriteSheet {
+    pub fn new(
+        texture: Handle<Texture>,
+        texture_size: (u32, u32),
+        region: AtlasRegion,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Result<Self, GenesisError> {
+        if frame_width == 0
+            || frame_height == 0
+            || frame_width > region.width
+            || frame_height > region.height
+        {
+            return Err(GenesisError::Atlas(format!(
+                "frames of {}x{} don't fit in a sprite sheet of {}x{}",
+                frame_width, frame_height, region.width, region.height
+            )));
+        }
+
+        Ok(SpriteSheet {
+            texture,
+            frame_width,
+            frame_height,
+            columns: region.width / frame_width,
+            rows: region.height / frame_height,
+            texture_size,
+            region,
+        })
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    ///Region of `frame` in pixels, wrapping around past the last frame
+    pub fn region(&self, frame: u32) -> AtlasRegion {
+        let frame = frame % self.frame_count();
+
+        AtlasRegion {
+            x: self.region.x + frame % self.columns * self.frame_width,
+            y: self.region.y + frame / self.columns * self.frame_height,
+            width: self.frame_width,
+            height: self.frame_height,
+        }
+    }
+
+    pub fn uv_rect(&self, frame: u32) -> [f32; 4] {
+        self.region(frame)
+            .uv_rect(self.texture_size.0, self.texture_size.1)
+    }
+}
+
+///Packs `images` into a single image no larger than `max_size` on either
+///side, returning it with the region of every image
+pub(crate) fn pack_images(
+    images: &[(&str, &RgbaImage)],
+    max_size: u32,
+) -> Result<(RgbaImage, HashMap<String, AtlasRegion>), GenesisError> {
+    let sizes: Vec<(u32, u32)> = images.iter().map(|(_, image)| image.dimensions()).collect();
+    let packing = shelf_pack(&sizes, max_size)?;
+
+    let mut atlas = RgbaImage::new(packing.width, packing.height);
+    let mut regions = HashMap::with_capacity(images.len());
+
+    for ((name, image), (x, y)) in images.iter().zip(packing.positions) {
+        image::imageops::replace(&mut atlas, *image, x as i64, y as i64);
+        regions.insert(
+            name.to_string(),
+            AtlasRegion {
+                x,
+                y,
+                width: image.width(),
+                height: image.height(),
+            },
+        );
+    }
+
+    Ok((atlas, regions))
+}
+
+///Size of a packed atlas and the position of every rectangle in it
+struct Packing {
+    width: u32,
+    height: u32,
+    positions: Vec<(u32, u32)>,
+}
+
+///Finds the narrowest power of two width, starting from a roughly square
+///atlas, whose shelves fit in `max_size`
+fn shelf_pack(sizes: &[(u32, u32)], max_size: u32) -> Result<Packing, GenesisError> {
+    let widest = sizes.iter().map(|&(width, _)| width).max().unwrap_or(1);
+    let area: u64 = sizes
+        .iter()
+        .map(|&(width, height)| (width + PADDING) as u64 * (height + PADDING) as u64)
+        .sum();
+
+    let mut width = widest
+        .max((area as f64).sqrt().ceil() as u32)
+        .next_power_of_two()
+        .min(max_size);
+
+    loop {
+        let (height, positions) = pack_shelves(sizes, width);
+
+        if widest <= width && height <= max_size {
+            return Ok(Packing {
+                width,
+                height: height.max(1),
+                positions,
+            });
+        }
+
+        if width >= max_size {
+            return Err(GenesisError::Atlas(format!(
+                "{} images don't fit in a {}x{} texture",
+                sizes.len(),
+                max_size,
+                max_size
+            )));
+        }
+
+        width = (width * 2).min(max_size);
+    }
+}
+
+///Places the rectangles tallest first on shelves `width` pixels wide, each
+///as tall as its first rectangle, and returns the height used with the
+///position of every rectangle in the order they were given
+fn pack_shelves(sizes: &[(u32, u32)], width: u32) -> (u32, Vec<(u32, u32)>) {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| Reverse(sizes[index].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+    for index in order {
+        let (rect_width, rect_height) = sizes[index];
+
+        if x > 0 && x + rect_width > width {
+            y += shelf_height;
+            x = 0;
+            shelf_height = 0;
+        }
+
+        positions[index] = (x, y);
+        x += rect_width + PADDING;
+        shelf_height = shelf_height.max(rect_height + PADDING);
+    }
+
+    (y + shelf_height, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn overlaps(a: ((u32, u32), (u32, u32)), b: ((u32, u32), (u32, u32))) -> bool {
+        let (((ax, ay), (aw, ah)), ((bx, by), (bw, bh))) = (a, b);
+        ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+    }
+
+    #[test]
+    fn packs_without_overlaps_inside_the_max_size() {
+        let sizes = [
+            (30, 10),
+            (5, 40),
+            (16, 16),
+            (64, 8),
+            (1, 1),
+            (20, 33),
+            (7, 7),
+            (40, 12),
+        ];
+        let packing = shelf_pack(&sizes, 128).unwrap();
+
+        assert!(packing.width <= 128 && packing.height <= 128);
+        assert!(packing.width.is_power_of_two());
+
+        let rects: Vec<_> = packing.positions.iter().copied().zip(sizes).collect();
+        for (i, &(position, size)) in rects.iter().enumerate() {
+            assert!(position.0 + size.0 <= packing.width);
+            assert!(position.1 + size.1 <= packing.height);
+
+            for &other in &rects[i + 1..] {
+                assert!(!overlaps((position, size), other));
+            }
+        }
+    }
+
+    #[test]
+    fn fails_when_the_images_do_not_fit() {
+        assert!(matches!(
+            shelf_pack(&[(100, 100), (100, 100)], 128),
+            Err(GenesisError::Atlas(_))
+        ));
+        assert!(matches!(
+            shelf_pack(&[(200, 10)], 128),
+            Err(GenesisError::Atlas(_))
+        ));
+    }
+
+    #[test]
+    fn copies_images_into_their_regions() {
+        let red = RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 255]));
+        let blue = RgbaImage::from_pixel(2, 4, Rgba([0, 0, 255, 255]));
+        let (atlas, regions) = pack_images(&[("red", &red), ("blue", &blue)], 64).unwrap();
+
+        for (name, image) in [("red", &red), ("blue", &blue)] {
+            let region = regions[name];
+            assert_eq!((region.width, region.height), image.dimensions());
+
+            for (x, y, pixel) in image.enumerate_pixels() {
+                assert_eq!(atlas.get_pixel(region.x + x, region.y + y), pixel);
+            }
+        }
+    }
+}
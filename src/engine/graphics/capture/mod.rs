@@ -0,0 +1,96 @@
+use std::sync::mpsc;
+
+use image::RgbaImage;
+
+///Size of the buffer `read_texture` copies a texture of `width` by `height`
+///pixels into
+pub fn buffer_size(width: u32, height: u32) -> wgpu::BufferAddress {
+    padded_bytes_per_row(width) as wgpu::BufferAddress * height as wgpu::BufferAddress
+}
+
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (4 * width).div_ceil(align) * align
+}
+
+///Copies the first mip level of `texture` into a mappable buffer and returns
+///its pixels as an `RgbaImage`. Rows are padded to
+///`COPY_BYTES_PER_ROW_ALIGNMENT` for the copy and unpadded on the way back,
+///and BGRA textures are swizzled so the result is always RGBA.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> RgbaImage {
+    let width = texture.width();
+    let height = texture.height();
+
+    let unpadded_bytes_per_row = 4 * width;
+    let padded_bytes_per_row = padded_bytes_per_row(width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture buffer"),
+        size: buffer_size(width, height),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Capture Encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("Capture buffer was dropped before being mapped")
+        .expect("Unable to map capture buffer");
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    if matches!(
+        texture.format(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+    ) {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    RgbaImage::from_raw(width, height, pixels).expect("Captured pixels do not match texture size")
+}
@@ -0,0 +1,76 @@
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+///Depth buffer of the main render pass, shared by every pipeline.
+///`Graphics` recreates it whenever the render target changes size or sample
+///count.
+pub struct DepthTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+}
+
+impl DepthTexture {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, sample_count: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        DepthTexture { texture, view }
+    }
+}
+
+///How a pipeline tests its fragments against the depth buffer and whether
+///it writes to it.
+///The default, `DepthOptions::enabled()`, keeps the closest fragment, and
+///lets later draws at the same depth through so flat scenes still draw in
+///submission order. Transparent geometry usually wants `write_enabled:
+///false`, and overlays and backgrounds can ignore depth entirely with
+///`DepthOptions::disabled()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthOptions {
+    pub compare: wgpu::CompareFunction,
+    pub write_enabled: bool,
+}
+
+impl DepthOptions {
+    pub fn enabled() -> Self {
+        DepthOptions {
+            compare: wgpu::CompareFunction::LessEqual,
+            write_enabled: true,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        DepthOptions {
+            compare: wgpu::CompareFunction::Always,
+            write_enabled: false,
+        }
+    }
+
+    pub fn state(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: self.write_enabled,
+            depth_compare: self.compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+impl Default for DepthOptions {
+    fn default() -> Self {
+        DepthOptions::enabled()
+    }
+}
@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+use crate::engine::assets::handle::Handle;
+
+use super::{
+    instance::{Instance, InstanceBuffer},
+    mesh::Mesh,
+};
+
+///A mesh drawn by a pipeline. The bind groups come from the pipeline: its
+///texture, the camera and its uniforms. Draws without `instances` draw the
+///mesh once.
+pub(crate) struct Draw {
+    pub pipeline_index: usize,
+    pub mesh: Handle<Mesh>,
+    pub instances: Option<InstanceBuffer>,
+}
+
+///Consecutive queued draws of the same mesh with the same pipeline, drawn
+///as a single instanced draw call
+pub(crate) struct Batch {
+    pub pipeline_index: usize,
+    pub mesh: Handle<Mesh>,
+    pub instances: Range<u32>,
+}
+
+///Refers to a draw added with `Graphics::add_draw`, used to stop drawing it
+///with `Graphics::remove_draw`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawHandle {
+    pub(crate) draw_index: usize,
+}
+
+///Draws submitted for a single frame, see `Graphics::queue_draw`.
+///Queued draws are turned into batches when the frame is rendered, and the
+///batches are kept until the next one so the frame can be rendered again
+///for captures.
+#[derive(Default)]
+pub(crate) struct DrawQueue {
+    queued: Vec<(usize, Handle<Mesh>, Instance)>,
+    pub batches: Vec<Batch>,
+    pub instances: Option<InstanceBuffer>,
+}
+
+impl DrawQueue {
+    pub fn push(&mut self, pipeline_index: usize, mesh: &Handle<Mesh>, instance: Instance) {
+        self.queued.push((pipeline_index, mesh.clone(), instance));
+    }
+
+    ///Batches the queued draws, keeping the order they were submitted in,
+    ///uploads their instances and empties the queue for the next frame
+    pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.batches.clear();
+        let mut instances = Vec::with_capacity(self.queued.len());
+
+        for (pipeline_index, mesh, instance) in self.queued.drain(..) {
+            let index = instances.len() as u32;
+            instances.push(instance);
+
+            match self.batches.last_mut() {
+                Some(batch) if batch.pipeline_index == pipeline_index && batch.mesh == mesh => {
+                    batch.instances.end = index + 1;
+                }
+                _ => self.batches.push(Batch {
+                    pipeline_index,
+                    mesh,
+                    instances: index..index + 1,
+                }),
+            }
+        }
+
+        match &mut self.instances {
+            Some(buffer) => buffer.write(device, queue, &instances),
+            None if !instances.is_empty() => {
+                self.instances = Some(InstanceBuffer::new(device, queue, &instances))
+            }
+            None => {}
+        }
+    }
+}
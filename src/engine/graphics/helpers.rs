@@ -1,12 +1,10 @@
-use std::{fs::File, io::Read};
+use std::{fs, path::Path};
 
-pub fn file_contents(file_path: &str) -> String {
-    let mut file =
-        File::open(file_path).expect(format!("Unable to open: {:?}", file_path).as_str());
+use crate::engine::error::GenesisError;
 
-    let mut data = String::new();
-
-    file.read_to_string(&mut data).unwrap();
-
-    data
+pub fn file_contents(file_path: &Path) -> Result<String, GenesisError> {
+    fs::read_to_string(file_path).map_err(|source| GenesisError::Io {
+        path: file_path.to_path_buf(),
+        source,
+    })
 }
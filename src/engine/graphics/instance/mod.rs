@@ -0,0 +1,123 @@
+use cgmath::Matrix4;
+
+use super::draw::DrawHandle;
+
+///Per-instance data of an instanced draw, see `Graphics::add_instanced_draw`.
+///Shaders read it as vertex attributes: the columns of the model matrix at
+///locations 8 to 11, the tint at 12 and the UV rect at 13, leaving the lower
+///locations to the vertex layout.
+///The UV rect is the offset and size of the region of the texture the
+///instance samples, `[x, y, width, height]` in texture coordinates.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance {
+    pub model: [[f32; 4]; 4],
+    pub tint: [f32; 4],
+    pub uv_rect: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        8 => Float32x4,
+        9 => Float32x4,
+        10 => Float32x4,
+        11 => Float32x4,
+        12 => Float32x4,
+        13 => Float32x4,
+    ];
+
+    pub fn new(model: Matrix4<f32>) -> Self {
+        Instance {
+            model: model.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_uv_rect(mut self, uv_rect: [f32; 4]) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+///An untransformed, untinted instance sampling the whole texture
+impl Default for Instance {
+    fn default() -> Self {
+        Instance {
+            model: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            tint: [1.0; 4],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+}
+
+///Instances of a draw uploaded to the GPU. The buffer only grows, so
+///updating the instances every frame doesn't reallocate it unless there are
+///more of them than ever before.
+pub(crate) struct InstanceBuffer {
+    pub buffer: wgpu::Buffer,
+    pub count: u32,
+    capacity: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) -> Self {
+        let capacity = instances.len().max(1).next_power_of_two();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&buffer, 0, bytemuck::cast_slice(instances));
+
+        InstanceBuffer {
+            buffer,
+            count: instances.len() as u32,
+            capacity,
+        }
+    }
+
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) {
+        if instances.len() > self.capacity {
+            *self = InstanceBuffer::new(device, queue, instances);
+            return;
+        }
+
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        self.count = instances.len() as u32;
+    }
+}
+
+///Refers to a draw added with `Graphics::add_instanced_draw`, used to change
+///its instances with `Graphics::update_instances`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceHandle {
+    pub(crate) draw_index: usize,
+}
+
+impl InstanceHandle {
+    ///The draw itself, to remove it with `Graphics::remove_draw`
+    pub fn draw(&self) -> DrawHandle {
+        DrawHandle {
+            draw_index: self.draw_index,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use wgpu::util::DeviceExt;
+
+use super::vertex_buffers::VertexLayout;
+
+///Axis aligned box enclosing every vertex of a mesh
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    pub fn from_positions<I: IntoIterator<Item = [f32; 3]>>(positions: I) -> Self {
+        let mut positions = positions.into_iter();
+        let Some(first) = positions.next() else {
+            return Bounds {
+                min: [0.0; 3],
+                max: [0.0; 3],
+            };
+        };
+
+        positions.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, position| Bounds {
+                min: [0, 1, 2].map(|axis| bounds.min[axis].min(position[axis])),
+                max: [0, 1, 2].map(|axis| bounds.max[axis].max(position[axis])),
+            },
+        )
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) / 2.0)
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| self.max[axis] - self.min[axis])
+    }
+}
+
+///Indices of a mesh. `u32` indices lift the 65,535 vertex limit of `u16`
+///ones at twice the memory, and meshes without indices draw their vertices
+///in order. An empty index list is the same as no indices.
+#[derive(Debug, Clone, Copy)]
+pub enum Indices<'i> {
+    None,
+    U16(&'i [u16]),
+    U32(&'i [u32]),
+}
+
+impl<'i> Indices<'i> {
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::None => 0,
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::U32(_) => wgpu::IndexFormat::Uint32,
+            Indices::None | Indices::U16(_) => wgpu::IndexFormat::Uint16,
+        }
+    }
+
+    fn bytes(&self) -> &'i [u8] {
+        match self {
+            Indices::None => &[],
+            Indices::U16(indices) => bytemuck::cast_slice(indices),
+            Indices::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+impl<'i> From<&'i [u16]> for Indices<'i> {
+    fn from(indices: &'i [u16]) -> Self {
+        Indices::U16(indices)
+    }
+}
+
+impl<'i> From<&'i [u32]> for Indices<'i> {
+    fn from(indices: &'i [u32]) -> Self {
+        Indices::U32(indices)
+    }
+}
+
+impl<'i> From<&'i Vec<u16>> for Indices<'i> {
+    fn from(indices: &'i Vec<u16>) -> Self {
+        Indices::U16(indices)
+    }
+}
+
+impl<'i> From<&'i Vec<u32>> for Indices<'i> {
+    fn from(indices: &'i Vec<u32>) -> Self {
+        Indices::U32(indices)
+    }
+}
+
+///Geometry uploaded to the GPU, independent of the pipelines drawing it.
+///The same mesh can be drawn by any number of pipelines, see
+///`Graphics::add_draw`, as long as they share its vertex layout. Meshes
+///without an `index_buffer` are drawn without indices.
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub vertex_count: u32,
+    pub index_buffer: Option<wgpu::Buffer>,
+    pub index_count: u32,
+    pub index_format: wgpu::IndexFormat,
+    pub bounds: Bounds,
+}
+
+impl Mesh {
+    pub fn new<V: VertexLayout>(device: &wgpu::Device, vertices: &[V], indices: Indices) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = (!indices.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: indices.bytes(),
+                usage: wgpu::BufferUsages::INDEX,
+            })
+        });
+
+        Mesh {
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            index_buffer,
+            index_count: indices.len() as u32,
+            index_format: indices.format(),
+            bounds: Bounds::from_positions(vertices.iter().filter_map(V::position)),
+        }
+    }
+
+    ///Records the draw call for `instances` of this mesh, the pipeline, its
+    ///bind groups and instance buffer must already be set
+    pub fn draw<'r>(&'r self, render_pass: &mut wgpu::RenderPass<'r>, instances: Range<u32>) {
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+
+        match &self.index_buffer {
+            Some(index_buffer) => {
+                render_pass.set_index_buffer(index_buffer.slice(..), self.index_format);
+                render_pass.draw_indexed(0..self.index_count, 0, instances);
+            }
+            None => render_pass.draw(0..self.vertex_count, instances),
+        }
+    }
+}
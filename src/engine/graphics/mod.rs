@@ -1,16 +1,46 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, collections::HashMap, ops::Range, path::Path};
 
+use cgmath::Matrix4;
+use log::{error, info, warn};
 use wgpu::{
     util::DeviceExt, Adapter, Device, DeviceDescriptor, Label, Queue, Surface, SurfaceConfiguration,
 };
 
-use self::{texture::Texture, vertex_buffers::Vertex};
+use self::{
+    atlas::{AtlasRegion, SpriteSheet, TextureAtlas},
+    depth::DepthOptions,
+    draw::{Draw, DrawHandle, DrawQueue},
+    instance::{Instance, InstanceBuffer, InstanceHandle},
+    mesh::{Indices, Mesh},
+    offscreen::OffscreenTarget,
+    shader::Shader,
+    sprite::{SpriteBatch, SpriteRenderer},
+    targets::RenderTargets,
+    texture::Texture,
+    uniform::{UniformHandle, Uniforms},
+    vertex_buffers::{Vertex, VertexLayout},
+};
 
-use super::camera::{camera_uniform::CameraUniform, Camera};
+use super::{
+    assets::{asset_server::AssetServer, handle::Handle, loader::Loaded, virtual_path, AssetRoot},
+    camera::{camera_uniform::CameraUniform, Camera},
+    error::GenesisError,
+};
 
+pub mod atlas;
+mod capture;
+pub mod depth;
+pub mod draw;
 mod helpers;
+pub mod instance;
+pub mod mesh;
+mod offscreen;
 mod pipeline;
+pub mod shader;
+pub mod sprite;
+mod targets;
 pub mod texture;
+pub mod uniform;
 pub mod vertex_buffers;
 
 const DEFAULT_CLEAR_COLOR: wgpu::Color = wgpu::Color {
@@ -25,55 +55,158 @@ pub struct Graphics<'a> {
     pub device: Device,
     pub queue: Queue,
     pub adapter: Adapter,
-    pub surface: Surface<'a>,
+    pub surface: Option<Surface<'a>>,
     pub config: SurfaceConfiguration,
     pub clear_color: wgpu::Color,
-    pipelines: Vec<pipeline::Pipeline>,
-    textures: Vec<Texture>,
-    uniform_buffers: Vec<wgpu::Buffer>,
-    uniform_bind_groups: Vec<(u32, wgpu::BindGroup)>,
-    uniform_bind_group_layouts: Vec<(u32, wgpu::BindGroupLayout)>,
+    pipelines: Vec<Option<pipeline::Pipeline>>,
+    draws: Vec<Option<Draw>>,
+    default_instance: InstanceBuffer,
+    draw_queue: DrawQueue,
+    sprite_renderer: Option<SpriteRenderer>,
+    textures: Vec<Handle<Texture>>,
     pub camera: Camera,
     camera_bind_group_layout: wgpu::BindGroupLayout,
     pub camera_uniform: CameraUniform,
     pub camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
-    pub window: &'a winit::window::Window,
+    offscreen: Option<OffscreenTarget>,
+    targets: RenderTargets,
+    sample_count: u32,
+    asset_root: AssetRoot,
+    hot_reload: bool,
+    assets: AssetServer,
+    pub window: Option<&'a winit::window::Window>,
 }
 
 impl<'a> Graphics<'a> {
     pub fn new(window: &'a winit::window::Window) -> Self {
+        Self::try_new(window).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new(window: &'a winit::window::Window) -> Result<Self, GenesisError> {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
         });
 
         let size = window.inner_size();
-        let surface = instance.create_surface(window).unwrap();
+        let surface = instance.create_surface(window)?;
 
         let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::default(),
             compatible_surface: Some(&surface),
             force_fallback_adapter: false,
         }))
-        .unwrap();
+        .ok_or(GenesisError::AdapterNotFound)?;
 
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
-                label: None,
-            },
-            None,
-        ))
-        .unwrap();
+        let (device, queue) = Self::request_device(&adapter)?;
 
         let config = surface
             .get_default_config(&adapter, size.width, size.height)
-            .unwrap();
+            .ok_or(GenesisError::SurfaceUnsupported)?;
 
         surface.configure(&device, &config);
 
+        Ok(Self::from_parts(
+            device,
+            queue,
+            adapter,
+            Some(surface),
+            config,
+            None,
+            Some(window),
+        ))
+    }
+
+    ///Creates a `Graphics` instance that is not attached to any window.
+    ///Frames are rendered into an offscreen texture of `width`x`height`, and a
+    ///software/fallback adapter is used when no hardware adapter is available.
+    pub fn new_headless(width: u32, height: u32) -> Graphics<'static> {
+        Self::try_new_headless(width, height).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new_headless(width: u32, height: u32) -> Result<Graphics<'static>, GenesisError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .or_else(|| {
+            info!("No hardware adapter found, requesting fallback adapter");
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            }))
+        })
+        .ok_or(GenesisError::AdapterNotFound)?;
+
+        let (device, queue) = Self::request_device(&adapter)?;
+
+        let config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: offscreen::OFFSCREEN_FORMAT,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        let offscreen = OffscreenTarget::new(&device, config.width, config.height, config.format);
+
+        info!(
+            "Headless graphics created: {}x{} using {:?}",
+            config.width,
+            config.height,
+            adapter.get_info().name
+        );
+
+        Ok(Graphics::from_parts(
+            device,
+            queue,
+            adapter,
+            None,
+            config,
+            Some(offscreen),
+            None,
+        ))
+    }
+
+    fn request_device(adapter: &Adapter) -> Result<(Device, Queue), GenesisError> {
+        let device = pollster::block_on(adapter.request_device(
+            &DeviceDescriptor {
+                required_features: adapter.features()
+                    & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+                //The adapter's texture and buffer sizes, so scaled captures
+                //aren't held to the 2048 pixels of the WebGL2 defaults
+                required_limits: wgpu::Limits {
+                    max_buffer_size: adapter.limits().max_buffer_size,
+                    ..wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
+                },
+                label: None,
+            },
+            None,
+        ))?;
+
+        Ok(device)
+    }
+
+    fn from_parts(
+        device: Device,
+        queue: Queue,
+        adapter: Adapter,
+        surface: Option<Surface<'a>>,
+        config: SurfaceConfiguration,
+        offscreen: Option<OffscreenTarget>,
+        window: Option<&'a winit::window::Window>,
+    ) -> Self {
         let camera = Camera {
             eye: (0.0, 1.0, 2.0).into(),
             target: (0.0, 0.0, 0.0).into(),
@@ -117,6 +250,9 @@ impl<'a> Graphics<'a> {
             label: Some("camera_binding_group"),
         });
 
+        let targets = RenderTargets::new(&device, config.width, config.height, config.format, 1);
+        let default_instance = InstanceBuffer::new(&device, &queue, &[Instance::default()]);
+
         Graphics {
             device,
             queue,
@@ -125,31 +261,171 @@ impl<'a> Graphics<'a> {
             config,
             clear_color: DEFAULT_CLEAR_COLOR,
             pipelines: Vec::new(),
+            draws: Vec::new(),
+            default_instance,
+            draw_queue: DrawQueue::default(),
+            sprite_renderer: None,
             textures: Vec::new(),
-            uniform_buffers: Vec::new(),
-            uniform_bind_groups: Vec::new(),
-            uniform_bind_group_layouts: Vec::new(),
             camera,
             camera_uniform,
             camera_buffer,
             camera_bind_group,
             camera_bind_group_layout,
+            offscreen,
+            targets,
+            sample_count: 1,
+            asset_root: AssetRoot::default(),
+            hot_reload: cfg!(debug_assertions),
+            assets: AssetServer::new(),
             window,
         }
     }
 
+    ///Returns true when this instance renders into an offscreen texture
+    ///instead of a window surface
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    ///Resizes the render target, reconfiguring the surface or recreating
+    ///the offscreen texture when running headless, along with the depth
+    ///buffer and multisampled target
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.config.width = width;
+        self.config.height = height;
+
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+
+        self.targets = self.create_targets(width, height);
+
+        if self.offscreen.is_some() {
+            self.offscreen = Some(OffscreenTarget::new(
+                &self.device,
+                width,
+                height,
+                self.config.format,
+            ));
+        }
+    }
+
+    ///Sets how many samples per pixel are rendered for anti-aliasing. The
+    ///frame is rendered into a multisampled texture that is resolved into
+    ///the surface, and every pipeline is rebuilt to match. Counts the
+    ///adapter doesn't support fall back to the closest lower one.
+    pub fn set_sample_count(&mut self, requested: u32) {
+        let sample_count = self
+            .supported_sample_counts()
+            .into_iter()
+            .filter(|supported| *supported <= requested)
+            .max()
+            .unwrap_or(1);
+
+        if sample_count != requested {
+            warn!(
+                "Sample count {} is not supported, using {}",
+                requested, sample_count
+            );
+        }
+
+        if sample_count == self.sample_count {
+            return;
+        }
+
+        self.sample_count = sample_count;
+        self.targets = self.create_targets(self.config.width, self.config.height);
+
+        for pipeline_index in 0..self.pipelines.len() {
+            self.rebuild_pipeline(pipeline_index);
+        }
+
+        info!("Sample count set to: {}", sample_count);
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    ///Sample counts usable for both the color and depth targets. Without
+    ///adapter specific format features only 1 and 4 are guaranteed.
+    pub fn supported_sample_counts(&self) -> Vec<u32> {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            return vec![1, 4];
+        }
+
+        let color = self.adapter.get_texture_format_features(self.config.format);
+        let depth = self
+            .adapter
+            .get_texture_format_features(depth::DEPTH_FORMAT);
+
+        color
+            .flags
+            .supported_sample_counts()
+            .into_iter()
+            .filter(|count| depth.flags.sample_count_supported(*count))
+            .collect()
+    }
+
+    fn create_targets(&self, width: u32, height: u32) -> RenderTargets {
+        RenderTargets::new(
+            &self.device,
+            width,
+            height,
+            self.config.format,
+            self.sample_count,
+        )
+    }
+
+    ///Reads the last rendered frame back from the GPU.
+    ///Only available when running headless, returns `None` otherwise.
+    pub fn read_frame(&self) -> Option<image::RgbaImage> {
+        self.offscreen
+            .as_ref()
+            .map(|offscreen| capture::read_texture(&self.device, &self.queue, &offscreen.texture))
+    }
+
     pub fn set_clear_color(&mut self, color: wgpu::Color) {
         self.clear_color = color;
     }
 
     pub fn render(&mut self) {
-        let frame = self
-            .surface
-            .get_current_texture()
-            .expect("Unable to get current texture");
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.draw_queue.flush(&self.device, &self.queue);
+
+        let frame = self.surface.as_ref().map(|surface| {
+            surface
+                .get_current_texture()
+                .expect("Unable to get current texture")
+        });
+        let view = match (&frame, &self.offscreen) {
+            (Some(frame), _) => frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            (None, Some(offscreen)) => offscreen
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            (None, None) => panic!("Graphics has no render target"),
+        };
+
+        self.render_to(&view, &self.targets);
+
+        if let Some(frame) = frame {
+            frame.present();
+        }
+    }
+
+    ///Records and submits the render pass for every draw into `view`, then
+    ///for the draws queued for this frame, using the depth buffer and
+    ///multisampled texture of `targets`
+    fn render_to(&self, view: &wgpu::TextureView, targets: &RenderTargets) {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -159,58 +435,393 @@ impl<'a> Graphics<'a> {
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.clear_color),
+                color_attachments: &[Some(targets.color_attachment(view, self.clear_color))],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &targets.depth.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
                         store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
-            for pipeline in &self.pipelines {
-                let vb = pipeline.vertex_buffer.as_ref();
-                let ib = pipeline.index_buffer.as_ref();
-                render_pass.set_pipeline(&pipeline.render_pipeline);
-
-                if let Some(texture_index) = pipeline.texture_index {
-                    let texture = &self.textures[texture_index];
-                    render_pass.set_bind_group(0, &texture.bind_group, &[]);
-                    render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
-                } else {
-                    render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-                }
+            for draw in self.draws.iter().flatten() {
+                let instances = draw.instances.as_ref().unwrap_or(&self.default_instance);
+                self.record_draw(
+                    &mut render_pass,
+                    draw.pipeline_index,
+                    &draw.mesh,
+                    instances,
+                    0..instances.count,
+                );
+            }
 
-                for (group, bind_group) in &self.uniform_bind_groups {
-                    render_pass.set_bind_group(*group, bind_group, &[]);
+            if let Some(instances) = &self.draw_queue.instances {
+                for batch in &self.draw_queue.batches {
+                    self.record_draw(
+                        &mut render_pass,
+                        batch.pipeline_index,
+                        &batch.mesh,
+                        instances,
+                        batch.instances.clone(),
+                    );
                 }
-
-                render_pass.set_vertex_buffer(0, vb.unwrap().slice(..));
-                render_pass.set_index_buffer(ib.unwrap().slice(..), wgpu::IndexFormat::Uint16);
-                render_pass.draw_indexed(0..pipeline.index_count, 0, 0..1)
             }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        frame.present();
     }
 
+    ///Sets the pipeline at `pipeline_index` with its bind groups and records
+    ///`instances` of `mesh`, skipping pipelines that aren't built yet and
+    ///meshes that are gone
+    fn record_draw<'r>(
+        &'r self,
+        render_pass: &mut wgpu::RenderPass<'r>,
+        pipeline_index: usize,
+        mesh: &Handle<Mesh>,
+        instance_buffer: &'r InstanceBuffer,
+        instances: Range<u32>,
+    ) {
+        let Some(pipeline) = &self.pipelines[pipeline_index] else {
+            return;
+        };
+        let Some(render_pipeline) = &pipeline.render_pipeline else {
+            return;
+        };
+        let Some(mesh) = self.assets.meshes.get(mesh) else {
+            return;
+        };
+
+        render_pass.set_pipeline(render_pipeline);
+
+        if let Some(texture) = pipeline
+            .texture
+            .as_ref()
+            .and_then(|texture| self.assets.textures.get(texture))
+        {
+            render_pass.set_bind_group(0, &texture.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+        } else {
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        }
+
+        if let Some(uniforms) = &pipeline.uniforms {
+            let group = pipeline::uniform_group(pipeline.texture.is_some());
+            render_pass.set_bind_group(group, &uniforms.bind_group, &[]);
+        }
+
+        if pipeline.instanced {
+            render_pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+        }
+
+        mesh.draw(render_pass, instances);
+    }
+
+    ///Saves the current frame as a PNG at `file_path`.
+    ///Headless instances copy their offscreen texture directly, windowed ones
+    ///render the current pipelines again into an offscreen texture since the
+    ///surface texture is gone once presented.
+    pub fn capture_frame<P: AsRef<Path>>(&self, file_path: P) -> Result<(), image::ImageError> {
+        self.capture_frame_scaled(file_path, 1)
+    }
+
+    ///Same as `capture_frame` but renders at `scale` times the current
+    ///resolution. The scale is reduced, with a warning, so neither side goes
+    ///past the largest texture the adapter supports, usually 8192 or 16384
+    ///pixels, and so the frame fits in the largest buffer it supports.
+    pub fn capture_frame_scaled<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        scale: u32,
+    ) -> Result<(), image::ImageError> {
+        let image = match (&self.offscreen, scale) {
+            (Some(offscreen), 1) => {
+                capture::read_texture(&self.device, &self.queue, &offscreen.texture)
+            }
+            _ => {
+                let limits = self.device.limits();
+                let largest_side = self.config.width.max(self.config.height);
+                let mut max_scale = (limits.max_texture_dimension_2d / largest_side).max(1);
+                while max_scale > 1
+                    && capture::buffer_size(
+                        self.config.width * max_scale,
+                        self.config.height * max_scale,
+                    ) > limits.max_buffer_size
+                {
+                    max_scale -= 1;
+                }
+
+                if scale > max_scale {
+                    warn!(
+                        "Capture scale {} reduced to {}, the largest the device can capture",
+                        scale, max_scale
+                    );
+                }
+                let scale = scale.clamp(1, max_scale);
+
+                let width = self.config.width * scale;
+                let height = self.config.height * scale;
+
+                let target = OffscreenTarget::new(&self.device, width, height, self.config.format);
+                self.render_to(
+                    &target
+                        .texture
+                        .create_view(&wgpu::TextureViewDescriptor::default()),
+                    &self.create_targets(width, height),
+                );
+
+                capture::read_texture(&self.device, &self.queue, &target.texture)
+            }
+        };
+
+        image.save(file_path.as_ref())?;
+        info!("Frame captured to: {:?}", file_path.as_ref());
+
+        Ok(())
+    }
+
+    ///Loads a texture and returns its index, which stays valid for as long
+    ///as `Graphics` lives. See `load_texture_handle` for textures that can be
+    ///freed once unused.
     #[track_caller]
     pub fn load_texture(&mut self, file_path: &str) -> usize {
-        let current_dir = std::env::current_dir().unwrap();
-        let caller_location = std::panic::Location::caller().file();
-        let parent = Path::new(caller_location).parent().unwrap();
-        let absolute_path = current_dir.join(parent).join(file_path);
+        self.try_load_texture(file_path)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    #[track_caller]
+    pub fn try_load_texture(&mut self, file_path: &str) -> Result<usize, GenesisError> {
+        let handle = self.try_load_texture_handle(file_path)?;
+        self.textures.push(handle);
+
+        Ok(self.textures.len() - 1)
+    }
+
+    ///Returns the handle of a texture loaded with `load_texture`
+    pub fn texture_handle(&self, texture_index: usize) -> Option<&Handle<Texture>> {
+        self.textures.get(texture_index)
+    }
+
+    ///Loads a texture into the `AssetServer` and returns a handle to it.
+    ///Loading a path that is already loaded returns the same handle, and the
+    ///texture is freed once every handle to it is dropped.
+    #[track_caller]
+    pub fn load_texture_handle(&mut self, file_path: &str) -> Handle<Texture> {
+        self.try_load_texture_handle(file_path)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    #[track_caller]
+    pub fn try_load_texture_handle(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Handle<Texture>, GenesisError> {
+        let source = self.asset_root.source(file_path);
+        let path = source.path();
+
+        if let Some(handle) = self.assets.textures.find(&path) {
+            return Ok(handle);
+        }
+
+        let image = Texture::decode_bytes(&source.read()?, &path)?;
+        let texture = Texture::from_image(&image, self);
+
+        Ok(self.assets.add_texture(texture, Some(&path)))
+    }
+
+    ///Loads a WGSL shader into the `AssetServer` and returns a handle to it,
+    ///used to build pipelines with `add_pipeline`. Loading a path that is
+    ///already loaded returns the same handle.
+    #[track_caller]
+    pub fn load_shader_handle(&mut self, file_path: &str) -> Handle<Shader> {
+        self.try_load_shader_handle(file_path)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    #[track_caller]
+    pub fn try_load_shader_handle(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Handle<Shader>, GenesisError> {
+        let source = self.asset_root.source(file_path);
+        let path = source.path();
+
+        if let Some(handle) = self.assets.shaders.find(&path) {
+            return Ok(handle);
+        }
+
+        let shader = Shader::from_bytes(path, source.read()?)?;
+
+        Ok(self.assets.add_shader(shader))
+    }
+
+    ///Creates a texture from an encoded image held in memory, like one
+    ///bundled with `include_bytes!`. `name` identifies it in errors and logs.
+    ///Unlike path based loads, every call creates a new texture.
+    pub fn load_texture_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Handle<Texture> {
+        self.try_load_texture_from_bytes(name, bytes)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_load_texture_from_bytes(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<Handle<Texture>, GenesisError> {
+        let texture = Texture::from_bytes(name, bytes, self)?;
+
+        Ok(self.assets.add_texture(texture, None))
+    }
+
+    ///Packs the images at `file_paths` into a single texture, with a region
+    ///named after each path, see `TextureAtlas`. Every call creates a new
+    ///texture.
+    #[track_caller]
+    pub fn load_atlas(&mut self, file_paths: &[&str]) -> TextureAtlas {
+        self.try_load_atlas(file_paths)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    #[track_caller]
+    pub fn try_load_atlas(&mut self, file_paths: &[&str]) -> Result<TextureAtlas, GenesisError> {
+        let mut images = Vec::with_capacity(file_paths.len());
+        for file_path in file_paths {
+            let source = self.asset_root.source(file_path);
+            images.push(Texture::decode_bytes(&source.read()?, &source.path())?);
+        }
+
+        let named: Vec<(&str, &image::RgbaImage)> =
+            file_paths.iter().copied().zip(images.iter()).collect();
+
+        self.create_atlas(&named)
+    }
+
+    ///Packs decoded `images` into a single texture, with a region for each
+    ///under the name it is paired with. Fails when they don't fit in the
+    ///largest texture the device supports.
+    pub fn create_atlas(
+        &mut self,
+        images: &[(&str, &image::RgbaImage)],
+    ) -> Result<TextureAtlas, GenesisError> {
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        let (image, regions) = atlas::pack_images(images, max_size)?;
+        let texture = Texture::from_image(&image, self);
+
+        info!(
+            "Texture atlas created: {} images in {}x{}",
+            images.len(),
+            image.width(),
+            image.height()
+        );
+
+        Ok(TextureAtlas {
+            texture: self.assets.add_texture(texture, None),
+            width: image.width(),
+            height: image.height(),
+            regions,
+        })
+    }
+
+    ///Loads the texture at `file_path` as a grid of `frame_width` by
+    ///`frame_height` frames, see `SpriteSheet`
+    #[track_caller]
+    pub fn load_sprite_sheet(
+        &mut self,
+        file_path: &str,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> SpriteSheet {
+        self.try_load_sprite_sheet(file_path, frame_width, frame_height)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    #[track_caller]
+    pub fn try_load_sprite_sheet(
+        &mut self,
+        file_path: &str,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Result<SpriteSheet, GenesisError> {
+        let texture = self.try_load_texture_handle(file_path)?;
+        let (width, height) = self
+            .assets
+            .textures
+            .get(&texture)
+            .map(|loaded| (loaded.width, loaded.height))
+            .ok_or_else(|| {
+                GenesisError::Atlas(format!(
+                    "{:?} is still loading in the background",
+                    file_path
+                ))
+            })?;
+        let region = AtlasRegion {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+
+        SpriteSheet::new(texture, (width, height), region, frame_width, frame_height)
+    }
 
-        let texture = Texture::load(absolute_path.to_str().unwrap(), self);
-        self.textures.push(texture);
-        self.textures.len() - 1
+    ///Creates a shader from WGSL source held in memory, like one bundled
+    ///with `include_str!`. `name` identifies it in errors and logs.
+    pub fn load_shader_from_str(&mut self, name: &str, source: &str) -> Handle<Shader> {
+        self.assets.add_shader(Shader {
+            path: virtual_path(name),
+            source: source.to_string(),
+        })
     }
 
+    ///Registers `bytes` as the contents of `file_path`, so every later load
+    ///of that path reads them instead of the filesystem, see `AssetRoot::embed`
+    pub fn embed_asset(&mut self, file_path: &str, bytes: &'static [u8]) {
+        self.asset_root.embed(file_path, bytes);
+    }
+
+    ///Starts loading a texture in the background and returns its handle
+    ///right away. The image is read and decoded on a worker thread, then
+    ///uploaded by `update_assets`. Pipelines using the texture are skipped
+    ///until it is ready, and `loading_progress` reports how far along the
+    ///pending loads are.
+    ///If the load fails the error is logged and the handle never resolves,
+    ///loading the same path again retries it.
+    #[track_caller]
+    pub fn load_texture_async(&mut self, file_path: &str) -> Handle<Texture> {
+        let source = self.asset_root.source(file_path);
+        self.assets.load_texture_async(source)
+    }
+
+    ///Starts loading a WGSL shader in the background, see `load_texture_async`
+    #[track_caller]
+    pub fn load_shader_async(&mut self, file_path: &str) -> Handle<Shader> {
+        let source = self.asset_root.source(file_path);
+        self.assets.load_shader_async(source)
+    }
+
+    ///Fraction of the background loads that finished, from `0.0` to `1.0`,
+    ///meant to drive a loading screen
+    pub fn loading_progress(&self) -> f32 {
+        self.assets.progress()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.assets.is_loading()
+    }
+
+    pub fn assets(&self) -> &AssetServer {
+        &self.assets
+    }
+
+    ///Loads a WGSL shader, registers a pipeline for it and returns the
+    ///pipeline index used to bind its uniforms.
+    ///If the shader fails to compile the error is logged and a magenta error
+    ///pipeline is registered in its place, so the rest of the scene keeps
+    ///rendering. Use `try_load_shader` to handle the error instead.
     #[track_caller]
     pub fn load_shader(
         &mut self,
@@ -218,83 +829,602 @@ impl<'a> Graphics<'a> {
         vertices: Option<&[Vertex]>,
         indices: Option<&[u16]>,
         texture_index: Option<usize>,
+    ) -> usize {
+        let shader = self.load_shader_handle(file_path);
+        let texture = texture_index.map(|index| self.textures[index].clone());
+
+        self.add_pipeline(&shader, vertices, indices, texture.as_ref())
+    }
+
+    #[track_caller]
+    pub fn try_load_shader(
+        &mut self,
+        file_path: &str,
+        vertices: Option<&[Vertex]>,
+        indices: Option<&[u16]>,
+        texture_index: Option<usize>,
+    ) -> Result<usize, GenesisError> {
+        let shader = self.try_load_shader_handle(file_path)?;
+        let texture = texture_index.map(|index| self.textures[index].clone());
+
+        self.try_add_pipeline(&shader, vertices, indices, texture.as_ref())
+    }
+
+    ///Registers a pipeline drawing `vertices` with `shader`, optionally
+    ///sampling `texture`, and returns its index. It's a shorthand for
+    ///`create_pipeline`, `create_mesh` and `add_draw`. Without `indices` the
+    ///vertices are drawn in order, use `create_mesh` for `u32` indices.
+    ///If the shader fails to compile the error is logged and the error
+    ///pipeline is used instead, see `try_add_pipeline` to handle it.
+    pub fn add_pipeline(
+        &mut self,
+        shader: &Handle<Shader>,
+        vertices: Option<&[Vertex]>,
+        indices: Option<&[u16]>,
+        texture: Option<&Handle<Texture>>,
+    ) -> usize {
+        let pipeline_index = self.create_pipeline(shader, texture);
+        self.add_mesh_draw(pipeline_index, vertices, indices);
+
+        pipeline_index
+    }
+
+    pub fn try_add_pipeline(
+        &mut self,
+        shader: &Handle<Shader>,
+        vertices: Option<&[Vertex]>,
+        indices: Option<&[u16]>,
+        texture: Option<&Handle<Texture>>,
+    ) -> Result<usize, GenesisError> {
+        let pipeline_index = self.try_create_pipeline(shader, texture)?;
+        self.add_mesh_draw(pipeline_index, vertices, indices);
+
+        Ok(pipeline_index)
+    }
+
+    fn add_mesh_draw(
+        &mut self,
+        pipeline_index: usize,
+        vertices: Option<&[Vertex]>,
+        indices: Option<&[u16]>,
     ) {
-        let current_dir = std::env::current_dir().unwrap();
-        let caller_location = std::panic::Location::caller().file();
-        let parent = Path::new(caller_location).parent().unwrap();
-        let absolute_path = current_dir.join(parent).join(file_path);
+        if let Some(vertices) = vertices {
+            let indices = indices.map_or(Indices::None, Indices::U16);
+            let mesh = self.create_mesh(vertices, indices);
+            self.add_draw(pipeline_index, &mesh);
+        }
+    }
 
-        let shader = self
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&helpers::file_contents(
-                    absolute_path.to_str().unwrap(),
-                ))),
+    ///Registers a pipeline drawing with `shader`, optionally sampling
+    ///`texture`, and returns its index. The pipeline keeps both assets
+    ///alive, and is built once both are loaded when either is still loading
+    ///in the background. It draws nothing until meshes are paired with it
+    ///with `add_draw`.
+    ///If the shader fails to compile the error is logged and the error
+    ///pipeline is used instead, see `try_create_pipeline` to handle it.
+    pub fn create_pipeline(
+        &mut self,
+        shader: &Handle<Shader>,
+        texture: Option<&Handle<Texture>>,
+    ) -> usize {
+        self.create_pipeline_with_layout::<Vertex>(shader, texture)
+    }
+
+    pub fn try_create_pipeline(
+        &mut self,
+        shader: &Handle<Shader>,
+        texture: Option<&Handle<Texture>>,
+    ) -> Result<usize, GenesisError> {
+        self.try_create_pipeline_with_layout::<Vertex>(shader, texture)
+    }
+
+    ///Same as `create_pipeline` for meshes made of `V` vertices instead of
+    ///`Vertex`, see `VertexLayout`
+    pub fn create_pipeline_with_layout<V: VertexLayout>(
+        &mut self,
+        shader: &Handle<Shader>,
+        texture: Option<&Handle<Texture>>,
+    ) -> usize {
+        let pipeline_index = self.push_pipeline(shader, texture, V::desc());
+        self.rebuild_pipeline(pipeline_index);
+
+        pipeline_index
+    }
+
+    pub fn try_create_pipeline_with_layout<V: VertexLayout>(
+        &mut self,
+        shader: &Handle<Shader>,
+        texture: Option<&Handle<Texture>>,
+    ) -> Result<usize, GenesisError> {
+        let pipeline_index = self.push_pipeline(shader, texture, V::desc());
+        let pipeline = self.pipeline(pipeline_index);
+
+        if self.pipeline_assets_loaded(pipeline) {
+            match self.build_pipeline(pipeline) {
+                Ok(render_pipeline) => {
+                    self.pipeline_mut(pipeline_index).render_pipeline = Some(render_pipeline)
+                }
+                Err(err) => {
+                    self.pipelines.pop();
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(pipeline_index)
+    }
+
+    ///Uploads `vertices` and `indices` to the GPU as a mesh, which is freed
+    ///once every handle to it is dropped. `indices` can be a `u16` or `u32`
+    ///slice, or `Indices::None` to draw the vertices in order. The mesh can
+    ///be drawn by pipelines created for the same vertex type.
+    pub fn create_mesh<'i, V: VertexLayout, I: Into<Indices<'i>>>(
+        &mut self,
+        vertices: &[V],
+        indices: I,
+    ) -> Handle<Mesh> {
+        let mesh = Mesh::new(&self.device, vertices, indices.into());
+
+        self.assets.add_mesh(mesh)
+    }
+
+    ///Draws `mesh` with the pipeline at `pipeline_index` every frame, using
+    ///the pipeline texture, camera and uniforms. A mesh can be drawn by any
+    ///number of pipelines and a pipeline can draw any number of meshes.
+    ///The draw keeps the mesh alive until it is removed with `remove_draw`.
+    pub fn add_draw(&mut self, pipeline_index: usize, mesh: &Handle<Mesh>) -> DrawHandle {
+        self.draws.push(Some(Draw {
+            pipeline_index,
+            mesh: mesh.clone(),
+            instances: None,
+        }));
+
+        DrawHandle {
+            draw_index: self.draws.len() - 1,
+        }
+    }
+
+    ///Stops drawing the draw behind `handle` and releases its mesh
+    pub fn remove_draw(&mut self, handle: &DrawHandle) {
+        match self.draws.get_mut(handle.draw_index) {
+            Some(draw @ Some(_)) => *draw = None,
+            _ => warn!("Draw not found: {:?}", handle),
+        }
+    }
+
+    ///Removes the pipeline at `pipeline_index` along with every draw using
+    ///it, releasing its shader, texture and meshes so they can be freed.
+    ///Indices of the other pipelines stay valid, and the removed index is
+    ///never reused.
+    pub fn remove_pipeline(&mut self, pipeline_index: usize) {
+        if self.pipelines[pipeline_index].take().is_none() {
+            warn!("Pipeline already removed: {}", pipeline_index);
+            return;
+        }
+
+        for draw in &mut self.draws {
+            if draw
+                .as_ref()
+                .is_some_and(|draw| draw.pipeline_index == pipeline_index)
+            {
+                *draw = None;
+            }
+        }
+    }
+
+    ///Draws `mesh` with the pipeline at `pipeline_index` once per instance
+    ///every frame. The pipeline becomes instanced, its shader reads the
+    ///instances as vertex attributes, see `Instance`, and its other draws
+    ///use a single default instance.
+    ///Returns a handle to change the instances later with `update_instances`.
+    pub fn add_instanced_draw(
+        &mut self,
+        pipeline_index: usize,
+        mesh: &Handle<Mesh>,
+        instances: &[Instance],
+    ) -> InstanceHandle {
+        self.make_instanced(pipeline_index);
+        self.draws.push(Some(Draw {
+            pipeline_index,
+            mesh: mesh.clone(),
+            instances: Some(InstanceBuffer::new(&self.device, &self.queue, instances)),
+        }));
+
+        InstanceHandle {
+            draw_index: self.draws.len() - 1,
+        }
+    }
+
+    ///Replaces the instances of the draw behind `handle`. The write is queued
+    ///and shows up in the next rendered frame, so it can be called every
+    ///frame from `Scene::draw` to move, add or remove instances.
+    pub fn update_instances(&mut self, handle: &InstanceHandle, instances: &[Instance]) {
+        match self
+            .draws
+            .get_mut(handle.draw_index)
+            .and_then(|draw| draw.as_mut()?.instances.as_mut())
+        {
+            Some(buffer) => buffer.write(&self.device, &self.queue, instances),
+            None => warn!("Instanced draw not found: {:?}", handle),
+        }
+    }
+
+    ///Draws `mesh` with the pipeline at `pipeline_index` and `transform` as
+    ///its model matrix in the current frame only. Meant to be called from
+    ///`Scene::draw` every frame for whatever should be on screen, on top of
+    ///the draws added with `add_draw`.
+    ///Queued draws are instanced: the pipeline becomes instanced and reads
+    ///the transform as an `Instance`, and consecutive draws of the same mesh
+    ///with the same pipeline are drawn in a single draw call.
+    pub fn queue_draw(
+        &mut self,
+        pipeline_index: usize,
+        mesh: &Handle<Mesh>,
+        transform: Matrix4<f32>,
+    ) {
+        self.queue_instance(pipeline_index, mesh, Instance::new(transform));
+    }
+
+    ///Same as `queue_draw` with a whole `Instance`, to set its tint and UV
+    ///rect as well
+    pub fn queue_instance(
+        &mut self,
+        pipeline_index: usize,
+        mesh: &Handle<Mesh>,
+        instance: Instance,
+    ) {
+        self.make_instanced(pipeline_index);
+        self.draw_queue.push(pipeline_index, mesh, instance);
+    }
+
+    ///Queues every sprite of `batch` for the current frame, see
+    ///`SpriteBatch`. Sprites are drawn with a built-in shader through the
+    ///camera, without depth testing, in a pipeline per texture that keeps
+    ///the texture alive.
+    pub fn draw_sprites(&mut self, batch: &SpriteBatch) {
+        let quad = self.sprite_renderer().quad.clone();
+
+        for (texture, sprite) in batch.sorted() {
+            let pipeline_index = self.sprite_pipeline(texture);
+            self.draw_queue
+                .push(pipeline_index, &quad, sprite.instance());
+        }
+    }
+
+    fn sprite_renderer(&mut self) -> &mut SpriteRenderer {
+        if self.sprite_renderer.is_none() {
+            let shader = self.load_shader_from_str("sprite.wgsl", sprite::SPRITE_SHADER);
+            let quad = self.create_mesh(sprite::SPRITE_QUAD, sprite::SPRITE_INDICES);
+
+            self.sprite_renderer = Some(SpriteRenderer {
+                shader,
+                quad,
+                pipelines: HashMap::new(),
             });
+        }
 
-        let mut bind_group_layouts = vec![&self.camera_bind_group_layout];
+        self.sprite_renderer
+            .as_mut()
+            .expect("The sprite renderer was just created")
+    }
 
-        for (_, layout) in &self.uniform_bind_group_layouts {
-            bind_group_layouts.push(layout);
+    fn sprite_pipeline(&mut self, texture: &Handle<Texture>) -> usize {
+        if let Some(&pipeline_index) = self.sprite_renderer().pipelines.get(&texture.id()) {
+            return pipeline_index;
         }
 
-        if let Some(tex_index) = texture_index {
-            let texture = &self.textures[tex_index];
+        let shader = self.sprite_renderer().shader.clone();
+        let pipeline_index = self.push_pipeline(&shader, Some(texture), Vertex::desc());
+        let pipeline = self.pipeline_mut(pipeline_index);
+        pipeline.instanced = true;
+        pipeline.depth = DepthOptions::disabled();
+        self.rebuild_pipeline(pipeline_index);
+
+        self.sprite_renderer()
+            .pipelines
+            .insert(texture.id(), pipeline_index);
+
+        pipeline_index
+    }
+
+    fn evict_sprite_pipelines(&mut self) {
+        let Some(sprite_renderer) = &mut self.sprite_renderer else {
+            return;
+        };
+
+        let pipelines = &self.pipelines;
+        let mut unused = Vec::new();
+        sprite_renderer.pipelines.retain(|_, &mut pipeline_index| {
+            let used = pipelines[pipeline_index]
+                .as_ref()
+                .and_then(|pipeline| pipeline.texture.as_ref())
+                .is_some_and(|texture| texture.count() > 1);
+            if !used {
+                unused.push(pipeline_index);
+            }
+            used
+        });
+
+        for pipeline_index in unused {
+            self.remove_pipeline(pipeline_index);
+        }
+    }
+
+    fn make_instanced(&mut self, pipeline_index: usize) {
+        if !self.pipeline(pipeline_index).instanced {
+            self.pipeline_mut(pipeline_index).instanced = true;
+            self.rebuild_pipeline(pipeline_index);
+        }
+    }
+
+    ///Sets where the relative paths given to `load_texture` and
+    ///`load_shader` are resolved from, see `AssetRoot` for the lookup order
+    pub fn set_asset_root(&mut self, asset_root: AssetRoot) {
+        self.asset_root = asset_root;
+    }
+
+    ///Enables or disables hot reloading of the files assets were loaded
+    ///from. It is enabled by default in debug builds.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+    }
+
+    ///Uploads the assets that finished loading in the background, hot
+    ///reloads changed ones and frees the ones that are no longer referenced
+    ///by any handle. Called by the engine once per frame.
+    pub fn update_assets(&mut self) {
+        self.finish_loads();
+        self.hot_reload();
+        self.evict_sprite_pipelines();
+        self.assets.collect_garbage();
+    }
+
+    fn finish_loads(&mut self) {
+        let finished = self.assets.finished_loads();
+        if finished.is_empty() {
+            return;
+        }
+
+        for loaded in finished {
+            match loaded {
+                Loaded::Texture(id, path, Ok(image)) => {
+                    let texture = Texture::from_image(&image, self);
+                    self.assets.finish_texture(id, &path, texture);
+                    info!("Texture loaded: {:?}", path);
+                }
+                Loaded::Shader(id, path, Ok(shader)) => {
+                    self.assets.finish_shader(id, shader);
+                    info!("Shader loaded: {:?}", path);
+                }
+                Loaded::Texture(id, _, Err(err)) => {
+                    self.assets.fail_texture(id);
+                    error!("{}", err)
+                }
+                Loaded::Shader(id, _, Err(err)) => {
+                    self.assets.fail_shader(id);
+                    error!("{}", err)
+                }
+            }
+        }
+
+        for pipeline_index in 0..self.pipelines.len() {
+            if self.pipelines[pipeline_index]
+                .as_ref()
+                .is_some_and(|pipeline| pipeline.render_pipeline.is_none())
+            {
+                self.rebuild_pipeline(pipeline_index);
+            }
+        }
+    }
+
+    ///Reloads the textures and shaders whose files changed on disk since they
+    ///were loaded. Textures are replaced in place, so existing indices and
+    ///handles stay valid. Pipelines using a changed shader are rebuilt
+    ///keeping their vertex/index buffers and texture bindings, and shaders that
+    ///fail to compile are replaced by the error pipeline until they are fixed.
+    pub fn hot_reload(&mut self) {
+        if !self.hot_reload {
+            return;
+        }
+
+        for (texture_id, file_path) in self.assets.changed_textures() {
+            match Texture::try_load(&file_path, self) {
+                Ok(texture) => {
+                    self.assets.textures.replace(texture_id, texture);
+                    info!("Texture reloaded: {:?}", file_path);
+                }
+                Err(err) => warn!("{}", err),
+            }
+        }
+
+        for (shader_id, file_path) in self.assets.changed_shaders() {
+            match Shader::load(&file_path) {
+                Ok(shader) => self.assets.shaders.replace(shader_id, shader),
+                Err(err) => {
+                    warn!("{}", err);
+                    continue;
+                }
+            };
+
+            for pipeline_index in 0..self.pipelines.len() {
+                if self.pipelines[pipeline_index]
+                    .as_ref()
+                    .is_some_and(|pipeline| pipeline.shader.id() == shader_id)
+                {
+                    self.rebuild_pipeline(pipeline_index);
+                }
+            }
+
+            info!("Shader reloaded: {:?}", file_path);
+        }
+    }
+
+    fn push_pipeline(
+        &mut self,
+        shader: &Handle<Shader>,
+        texture: Option<&Handle<Texture>>,
+        vertex_layout: wgpu::VertexBufferLayout<'static>,
+    ) -> usize {
+        self.pipelines.push(Some(pipeline::Pipeline::new(
+            shader.clone(),
+            texture.cloned(),
+            vertex_layout,
+        )));
+
+        self.pipelines.len() - 1
+    }
+
+    #[track_caller]
+    fn pipeline(&self, pipeline_index: usize) -> &pipeline::Pipeline {
+        self.pipelines[pipeline_index]
+            .as_ref()
+            .unwrap_or_else(|| panic!("Pipeline {} was removed", pipeline_index))
+    }
+
+    #[track_caller]
+    fn pipeline_mut(&mut self, pipeline_index: usize) -> &mut pipeline::Pipeline {
+        self.pipelines[pipeline_index]
+            .as_mut()
+            .unwrap_or_else(|| panic!("Pipeline {} was removed", pipeline_index))
+    }
+
+    fn create_pipeline_layout(&self, pipeline: &pipeline::Pipeline) -> wgpu::PipelineLayout {
+        let mut bind_group_layouts = vec![&self.camera_bind_group_layout];
+
+        if let Some(texture) = pipeline
+            .texture
+            .as_ref()
+            .and_then(|texture| self.assets.textures.get(texture))
+        {
             bind_group_layouts.insert(0, &texture.bind_group_layout);
         }
 
-        let pipeline_layout = self
-            .device
+        if let Some(uniforms) = &pipeline.uniforms {
+            bind_group_layouts.push(&uniforms.bind_group_layout);
+        }
+
+        self.device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: None,
                 bind_group_layouts: &bind_group_layouts,
                 push_constant_ranges: &[],
-            });
+            })
+    }
 
-        let swapchain_capabilities = self.surface.get_capabilities(&self.adapter);
-        let swapchain_format = swapchain_capabilities.formats[0];
+    fn pipeline_assets_loaded(&self, pipeline: &pipeline::Pipeline) -> bool {
+        self.assets.shaders.is_loaded(&pipeline.shader)
+            && pipeline
+                .texture
+                .as_ref()
+                .is_none_or(|texture| self.assets.textures.is_loaded(texture))
+    }
 
-        let contents = if let Some(vertices) = vertices {
-            vertices
-        } else {
-            &[]
+    ///Builds the pipeline if its assets are loaded, falling back to the error
+    ///pipeline when the shader fails to compile
+    fn rebuild_pipeline(&mut self, pipeline_index: usize) {
+        let Some(pipeline) = &self.pipelines[pipeline_index] else {
+            return;
         };
+        if !self.pipeline_assets_loaded(pipeline) {
+            return;
+        }
 
-        let index_contents = if let Some(indices) = indices {
-            indices
-        } else {
-            &[]
-        };
+        let render_pipeline = self.build_pipeline(pipeline).unwrap_or_else(|err| {
+            error!("{}", err);
+            self.create_error_pipeline(pipeline)
+        });
 
-        let vertex_buffer = self
+        self.pipeline_mut(pipeline_index).render_pipeline = Some(render_pipeline);
+    }
+
+    fn build_pipeline(
+        &self,
+        pipeline: &pipeline::Pipeline,
+    ) -> Result<wgpu::RenderPipeline, GenesisError> {
+        let shader = self
+            .assets
+            .shaders
+            .get(&pipeline.shader)
+            .expect("Pipelines are only built once their shader is loaded");
+        let pipeline_layout = self.create_pipeline_layout(pipeline);
+
+        self.create_render_pipeline(&shader.path, &shader.source, &pipeline_layout, pipeline)
+    }
+
+    ///Validates `source` and builds a pipeline from it. Errors that naga
+    ///doesn't catch, like a layout mismatch, are captured with an error scope
+    ///instead of panicking.
+    fn create_render_pipeline(
+        &self,
+        file_path: &Path,
+        source: &str,
+        pipeline_layout: &wgpu::PipelineLayout,
+        pipeline: &pipeline::Pipeline,
+    ) -> Result<wgpu::RenderPipeline, GenesisError> {
+        shader::validate(file_path, source)?;
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = self
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(contents),
-                usage: wgpu::BufferUsages::VERTEX,
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: file_path.to_str(),
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
             });
+        let render_pipeline = self.render_pipeline_from(&shader, pipeline_layout, pipeline);
 
-        let index_buffer = self
+        match pollster::block_on(self.device.pop_error_scope()) {
+            Some(err) => Err(GenesisError::Shader {
+                path: file_path.to_path_buf(),
+                line: None,
+                column: None,
+                message: err.to_string(),
+            }),
+            None => Ok(render_pipeline),
+        }
+    }
+
+    fn create_error_pipeline(&self, pipeline: &pipeline::Pipeline) -> wgpu::RenderPipeline {
+        let pipeline_layout = self.create_pipeline_layout(pipeline);
+        let camera_group = pipeline::camera_group(pipeline.texture.is_some());
+        let position_format = vertex_buffers::position_format(&pipeline.vertex_layout);
+
+        let shader = self
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(index_contents),
-                usage: wgpu::BufferUsages::INDEX,
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Error shader"),
+                source: wgpu::ShaderSource::Wgsl(Cow::Owned(shader::error_shader(
+                    camera_group,
+                    position_format,
+                ))),
             });
 
-        let render_pipeline = self
-            .device
+        self.render_pipeline_from(&shader, &pipeline_layout, pipeline)
+    }
+
+    fn render_pipeline_from(
+        &self,
+        shader: &wgpu::ShaderModule,
+        pipeline_layout: &wgpu::PipelineLayout,
+        pipeline: &pipeline::Pipeline,
+    ) -> wgpu::RenderPipeline {
+        let swapchain_format = self.config.format;
+        let mut vertex_buffers = vec![pipeline.vertex_layout.clone()];
+        if pipeline.instanced {
+            vertex_buffers.push(Instance::desc());
+        }
+
+        self.device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
                 label: None,
-                layout: Some(&pipeline_layout),
+                layout: Some(pipeline_layout),
                 vertex: wgpu::VertexState {
-                    module: &shader,
+                    module: shader,
                     entry_point: "vs_main",
-                    buffers: &[Vertex::desc()],
+                    buffers: &vertex_buffers,
                 },
                 fragment: Some(wgpu::FragmentState {
-                    module: &shader,
+                    module: shader,
                     entry_point: "fs_main",
                     targets: &[Some(wgpu::ColorTargetState {
                         format: swapchain_format,
@@ -314,26 +1444,38 @@ impl<'a> Graphics<'a> {
                     })],
                 }),
                 primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(pipeline.depth.state()),
+                multisample: wgpu::MultisampleState {
+                    count: self.sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
                 multiview: None,
-            });
-
-        let vertex_index_size = contents.len();
-        let index_count = index_contents.len();
+            })
+    }
 
-        self.pipelines.push(pipeline::Pipeline::new(
-            render_pipeline,
-            Some(vertex_buffer),
-            vertex_index_size as u32,
-            Some(index_buffer),
-            index_count as u32,
-            texture_index,
-        ));
+    ///Changes how the pipeline at `pipeline_index` tests and writes depth,
+    ///see `DepthOptions`. Pipelines test and write depth by default, use
+    ///`DepthOptions::disabled()` to draw a pipeline over everything drawn
+    ///before it.
+    pub fn set_pipeline_depth(&mut self, pipeline_index: usize, depth: DepthOptions) {
+        self.pipeline_mut(pipeline_index).depth = depth;
+        self.rebuild_pipeline(pipeline_index);
     }
 
-    pub fn bind_uniform<T: bytemuck::Pod>(&mut self, uniform_data: T, group: u32, binding: u32) {
-        let uniform_bufffer = self
+    ///Attaches a uniform buffer holding `uniform_data` to the pipeline at
+    ///`pipeline_index`, at `binding` of the pipeline uniform group.
+    ///Every uniform of a pipeline shares that group, which comes right after
+    ///the camera: `@group(1)` for untextured pipelines and `@group(2)` for
+    ///textured ones. Binding the same `binding` again replaces its buffer.
+    ///Returns a handle to change the value later with `update_uniform`.
+    pub fn bind_uniform<T: bytemuck::Pod>(
+        &mut self,
+        pipeline_index: usize,
+        uniform_data: T,
+        binding: u32,
+    ) -> UniformHandle<T> {
+        let uniform_buffer = self
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Uniform buffer"),
@@ -341,34 +1483,37 @@ impl<'a> Graphics<'a> {
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-        let uniform_bind_group_layout =
-            self.device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding,
-                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    }],
-                    label: Some("Uniform bind group layout"),
-                });
-
-        let uniform_bindg_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding,
-                resource: uniform_bufffer.as_entire_binding(),
-            }],
-            label: Some("Uniform Bind Group"),
-        });
+        let mut buffers = self
+            .pipeline_mut(pipeline_index)
+            .uniforms
+            .take()
+            .map(Uniforms::into_buffers)
+            .unwrap_or_default();
+        buffers.retain(|(bound, _)| *bound != binding);
+        buffers.push((binding, uniform_buffer));
+
+        let uniforms = Uniforms::new(&self.device, buffers);
+        self.pipeline_mut(pipeline_index).uniforms = Some(uniforms);
+        self.rebuild_pipeline(pipeline_index);
+
+        UniformHandle::new(pipeline_index, binding)
+    }
+
+    ///Writes `value` into the uniform behind `handle`. The write is queued
+    ///and shows up in the next rendered frame, so it can be called every
+    ///frame from `Scene::draw` for values like time, colors or transforms.
+    pub fn update_uniform<T: bytemuck::Pod>(&self, handle: &UniformHandle<T>, value: T) {
+        let buffer = self
+            .pipelines
+            .get(handle.pipeline_index)
+            .and_then(|pipeline| pipeline.as_ref()?.uniforms.as_ref())
+            .and_then(|uniforms| uniforms.buffer(handle.binding));
 
-        self.uniform_buffers.push(uniform_bufffer);
-        self.uniform_bind_groups.push((group, uniform_bindg_group));
-        self.uniform_bind_group_layouts
-            .push((group, uniform_bind_group_layout));
+        match buffer {
+            Some(buffer) => self
+                .queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(&[value])),
+            None => warn!("Uniform not found: {:?}", handle),
+        }
     }
 }
@@ -1,28 +1,52 @@
+use crate::engine::assets::handle::Handle;
+
+use super::{depth::DepthOptions, shader::Shader, texture::Texture, uniform::Uniforms};
+
+///A shader together with the vertex layout, texture, uniforms and depth
+///options it is drawn with. Instanced pipelines read `Instance`s from a
+///second vertex buffer. Pipelines hold no geometry, meshes are paired with
+///them by `Draw`s.
+///A pipeline has no `render_pipeline` while its shader or texture are still
+///loading in the background, and is skipped when rendering until they are.
+///
+///Bind groups are assigned the same way for every pipeline: the texture
+///comes first if there is one, then the camera, then the pipeline uniforms.
 pub struct Pipeline {
-    pub render_pipeline: wgpu::RenderPipeline,
-    pub vertex_buffer: Option<wgpu::Buffer>,
-    pub vertex_count: u32,
-    pub index_buffer: Option<wgpu::Buffer>,
-    pub index_count: u32,
-    pub texture_index: Option<usize>,
+    pub render_pipeline: Option<wgpu::RenderPipeline>,
+    pub shader: Handle<Shader>,
+    pub texture: Option<Handle<Texture>>,
+    pub uniforms: Option<Uniforms>,
+    pub depth: DepthOptions,
+    pub vertex_layout: wgpu::VertexBufferLayout<'static>,
+    pub instanced: bool,
 }
 
 impl Pipeline {
     pub fn new(
-        render_pipeline: wgpu::RenderPipeline,
-        vertex_buffer: Option<wgpu::Buffer>,
-        vertex_count: u32,
-        index_buffer: Option<wgpu::Buffer>,
-        index_count: u32,
-        texture_index: Option<usize>,
+        shader: Handle<Shader>,
+        texture: Option<Handle<Texture>>,
+        vertex_layout: wgpu::VertexBufferLayout<'static>,
     ) -> Self {
         Pipeline {
-            render_pipeline,
-            vertex_buffer,
-            vertex_count,
-            index_buffer,
-            index_count,
-            texture_index,
+            render_pipeline: None,
+            shader,
+            texture,
+            uniforms: None,
+            depth: DepthOptions::default(),
+            vertex_layout,
+            instanced: false,
         }
     }
 }
+
+pub fn camera_group(textured: bool) -> u32 {
+    if textured {
+        1
+    } else {
+        0
+    }
+}
+
+pub fn uniform_group(textured: bool) -> u32 {
+    camera_group(textured) + 1
+}
@@ -0,0 +1,119 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use wgpu::naga::{
+    self,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+
+use crate::engine::error::GenesisError;
+
+use super::helpers;
+
+///WGSL source loaded from `path`.
+///Pipelines are built from it with `Graphics::add_pipeline`, and rebuilt when
+///the file is hot reloaded.
+pub struct Shader {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+impl Shader {
+    ///Reads the WGSL source at `file_path`. The source is only validated
+    ///when a pipeline is built from it.
+    pub fn load(file_path: &Path) -> Result<Self, GenesisError> {
+        Ok(Shader {
+            path: file_path.to_path_buf(),
+            source: helpers::file_contents(file_path)?,
+        })
+    }
+
+    ///Creates a shader from the UTF-8 bytes of a WGSL file read from `path`
+    pub fn from_bytes(path: PathBuf, bytes: Vec<u8>) -> Result<Self, GenesisError> {
+        match String::from_utf8(bytes) {
+            Ok(source) => Ok(Shader { path, source }),
+            Err(err) => Err(GenesisError::Io {
+                path,
+                source: io::Error::new(io::ErrorKind::InvalidData, err),
+            }),
+        }
+    }
+}
+
+///Parses and validates WGSL `source` with naga before it reaches wgpu, so
+///errors can be reported with the file path, line and column instead of
+///panicking inside `create_shader_module`
+pub(crate) fn validate(file_path: &Path, source: &str) -> Result<(), GenesisError> {
+    let path = file_path.to_string_lossy();
+
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| {
+        let location = err.location(source);
+        GenesisError::Shader {
+            path: file_path.to_path_buf(),
+            line: location.map(|location| location.line_number),
+            column: location.map(|location| location.line_position),
+            message: err.emit_to_string_with_path(source, file_path),
+        }
+    })?;
+
+    Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|err| {
+            let location = err.location(source);
+            GenesisError::Shader {
+                path: file_path.to_path_buf(),
+                line: location.map(|location| location.line_number),
+                column: location.map(|location| location.line_position),
+                message: err.emit_to_string_with_path(source, &path),
+            }
+        })?;
+
+    Ok(())
+}
+
+///Source of the shader used in place of one that failed to compile.
+///It draws the geometry in plain magenta so broken materials stand out while
+///the rest of the scene keeps rendering. The camera is read from
+///`camera_group` to match the layout of the pipeline it replaces, and the
+///position from location 0 in `position_format`. Geometry without a float
+///position isn't drawn.
+pub(crate) fn error_shader(
+    camera_group: u32,
+    position_format: Option<wgpu::VertexFormat>,
+) -> String {
+    let (input, position) = match position_format {
+        Some(wgpu::VertexFormat::Float32x2) => (
+            "@location(0) position: vec2<f32>",
+            "vec4<f32>(position, 0.0, 1.0)",
+        ),
+        Some(wgpu::VertexFormat::Float32x3) => (
+            "@location(0) position: vec3<f32>",
+            "vec4<f32>(position, 1.0)",
+        ),
+        Some(wgpu::VertexFormat::Float32x4) => ("@location(0) position: vec4<f32>", "position"),
+        _ => ("", "vec4<f32>(0.0)"),
+    };
+
+    format!(
+        r#"
+struct CameraUniform {{
+    view_proj: mat4x4<f32>,
+}};
+
+@group({camera_group}) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@vertex
+fn vs_main({input}) -> @builtin(position) vec4<f32> {{
+    return camera.view_proj * {position};
+}}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {{
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+}}
+"#
+    )
+}
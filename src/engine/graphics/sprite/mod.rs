@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use cgmath::{Matrix4, Rad, Vector3};
+
+use crate::engine::assets::handle::{AssetId, Handle};
+
+use super::{
+    instance::Instance, mesh::Mesh, shader::Shader, texture::Texture, vertex_buffers::Vertex,
+};
+
+pub(crate) const SPRITE_SHADER: &str = include_str!("sprite.wgsl");
+
+///Unit quad centered on the origin every sprite is drawn with
+pub(crate) const SPRITE_QUAD: &[Vertex] = &[
+    Vertex {
+        position: [-0.5, -0.5, 0.0],
+        tex_coord: [0.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, -0.5, 0.0],
+        tex_coord: [1.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, 0.5, 0.0],
+        tex_coord: [1.0, 0.0],
+    },
+    Vertex {
+        position: [-0.5, 0.5, 0.0],
+        tex_coord: [0.0, 0.0],
+    },
+];
+
+pub(crate) const SPRITE_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
+///A textured quad drawn by a `SpriteBatch`, centered on `position` in the
+///XY plane of the world. `scale` is its size in world units, `rotation` is
+///in radians counterclockwise and `uv_rect` is the region of the texture it
+///shows, `[x, y, width, height]` in texture coordinates.
+///Sprites with a higher `z` are drawn on top of the ones below.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub position: [f32; 2],
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    pub tint: [f32; 4],
+    pub uv_rect: [f32; 4],
+    pub z: f32,
+}
+
+impl Sprite {
+    pub fn new(position: [f32; 2]) -> Self {
+        Sprite {
+            position,
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            tint: [1.0; 4],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            z: 0.0,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: [f32; 2]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_uv_rect(mut self, uv_rect: [f32; 4]) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn instance(&self) -> Instance {
+        let model =
+            Matrix4::from_translation(Vector3::new(self.position[0], self.position[1], 0.0))
+                * Matrix4::from_angle_z(Rad(self.rotation))
+                * Matrix4::from_nonuniform_scale(self.scale[0], self.scale[1], 1.0);
+
+        Instance::new(model)
+            .with_tint(self.tint)
+            .with_uv_rect(self.uv_rect)
+    }
+}
+
+///Sprites to draw in a frame with `Graphics::draw_sprites`.
+///Sprites are sorted by `z` and then grouped by texture, and each group of
+///consecutive sprites sharing a texture is a single instanced draw call.
+///Overlapping sprites with different textures should use different `z`s to
+///keep their order.
+#[derive(Default)]
+pub struct SpriteBatch {
+    sprites: Vec<(Handle<Texture>, Sprite)>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        SpriteBatch::default()
+    }
+
+    pub fn add(&mut self, texture: &Handle<Texture>, sprite: Sprite) {
+        self.sprites.push((texture.clone(), sprite));
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    ///Sprites in the order they are drawn
+    pub(crate) fn sorted(&self) -> Vec<&(Handle<Texture>, Sprite)> {
+        let mut sprites: Vec<_> = self.sprites.iter().collect();
+        sprites.sort_by(|(a_texture, a), (b_texture, b)| {
+            a.z.total_cmp(&b.z)
+                .then_with(|| a_texture.id().cmp(&b_texture.id()))
+        });
+
+        sprites
+    }
+}
+
+///Shader, quad and per texture pipelines shared by every sprite batch,
+///created the first time sprites are drawn. A texture's pipeline is removed
+///once the pipeline holds the last handle to it, so the texture can be freed.
+pub(crate) struct SpriteRenderer {
+    pub shader: Handle<Shader>,
+    pub quad: Handle<Mesh>,
+    pub pipelines: HashMap<AssetId, usize>,
+}
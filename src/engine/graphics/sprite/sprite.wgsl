@@ -0,0 +1,52 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var sprite_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var sprite_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coord: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(8) model_0: vec4<f32>,
+    @location(9) model_1: vec4<f32>,
+    @location(10) model_2: vec4<f32>,
+    @location(11) model_3: vec4<f32>,
+    @location(12) tint: vec4<f32>,
+    @location(13) uv_rect: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * model * vec4<f32>(vertex.position, 1.0);
+    out.tex_coord = instance.uv_rect.xy + vertex.tex_coord * instance.uv_rect.zw;
+    out.tint = instance.tint;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(sprite_texture, sprite_sampler, in.tex_coord) * in.tint;
+}
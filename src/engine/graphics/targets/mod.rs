@@ -0,0 +1,66 @@
+use super::depth::DepthTexture;
+
+///Attachments the main render pass draws into besides the final color
+///target: the depth buffer and, when MSAA is enabled, the multisampled color
+///texture that gets resolved into the final target at the end of the pass.
+///They all share the size and sample count of the frame they render.
+pub struct RenderTargets {
+    pub depth: DepthTexture,
+    pub multisample: Option<wgpu::TextureView>,
+}
+
+impl RenderTargets {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let multisample = (sample_count > 1).then(|| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Multisample texture"),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        RenderTargets {
+            depth: DepthTexture::new(device, width, height, sample_count),
+            multisample,
+        }
+    }
+
+    ///Color attachment drawing into `view`, through the multisampled
+    ///texture when there is one
+    pub fn color_attachment<'v>(
+        &'v self,
+        view: &'v wgpu::TextureView,
+        clear_color: wgpu::Color,
+    ) -> wgpu::RenderPassColorAttachment<'v> {
+        let (view, resolve_target) = match &self.multisample {
+            Some(multisample) => (multisample, Some(view)),
+            None => (view, None),
+        };
+
+        wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(clear_color),
+                store: wgpu::StoreOp::Store,
+            },
+        }
+    }
+}
@@ -1,20 +1,66 @@
 use std::{fs, path::Path};
 
+use image::RgbaImage;
+
+use crate::engine::{assets::virtual_path, error::GenesisError};
+
 use super::Graphics;
 
 pub struct Texture {
     pub bind_group: wgpu::BindGroup,
     pub bind_group_layout: wgpu::BindGroupLayout,
+    pub width: u32,
+    pub height: u32,
 }
 
 impl Texture {
     pub fn load(file_path: &str, graphics: &Graphics) -> Self {
-        let img_bytes = fs::read(file_path).unwrap();
-        let loaded_img = image::load_from_memory(&img_bytes).unwrap();
-        let img_rgba = loaded_img.to_rgba8();
+        Self::try_load(file_path, graphics).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_load<P: AsRef<Path>>(
+        file_path: P,
+        graphics: &Graphics,
+    ) -> Result<Self, GenesisError> {
+        let image = Self::decode(file_path)?;
+
+        Ok(Self::from_image(&image, graphics))
+    }
+
+    ///Decodes and uploads an encoded image held in memory, `name` is only
+    ///used to report errors
+    pub fn from_bytes(name: &str, bytes: &[u8], graphics: &Graphics) -> Result<Self, GenesisError> {
+        let image = Self::decode_bytes(bytes, &virtual_path(name))?;
+
+        Ok(Self::from_image(&image, graphics))
+    }
 
-        use image::GenericImageView;
-        let img_dimensions = loaded_img.dimensions();
+    ///Reads and decodes the image at `file_path` without touching the GPU,
+    ///so it can run on a worker thread
+    pub fn decode<P: AsRef<Path>>(file_path: P) -> Result<RgbaImage, GenesisError> {
+        let file_path = file_path.as_ref();
+        let img_bytes = fs::read(file_path).map_err(|source| GenesisError::Io {
+            path: file_path.to_path_buf(),
+            source,
+        })?;
+
+        Self::decode_bytes(&img_bytes, file_path)
+    }
+
+    ///Decodes an encoded image, `file_path` is only used to report errors
+    pub fn decode_bytes(img_bytes: &[u8], file_path: &Path) -> Result<RgbaImage, GenesisError> {
+        let loaded_img =
+            image::load_from_memory(img_bytes).map_err(|source| GenesisError::Image {
+                path: file_path.to_path_buf(),
+                source,
+            })?;
+
+        Ok(loaded_img.to_rgba8())
+    }
+
+    ///Uploads a decoded image to the GPU
+    pub fn from_image(img_rgba: &RgbaImage, graphics: &Graphics) -> Self {
+        let img_dimensions = img_rgba.dimensions();
 
         let texture_size = wgpu::Extent3d {
             width: img_dimensions.0,
@@ -40,7 +86,7 @@ impl Texture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &img_rgba,
+            img_rgba,
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * img_dimensions.0),
@@ -105,6 +151,8 @@ impl Texture {
         Texture {
             bind_group,
             bind_group_layout,
+            width: img_dimensions.0,
+            height: img_dimensions.1,
         }
     }
 }
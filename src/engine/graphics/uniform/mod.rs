@@ -0,0 +1,106 @@
+use std::{fmt, marker::PhantomData};
+
+///Uniform buffers attached to a single pipeline.
+///Every uniform of a pipeline lives in the same bind group, at the binding
+///chosen when it was bound, so a pipeline never uses more than one group for
+///them regardless of how many it has.
+pub struct Uniforms {
+    buffers: Vec<(u32, wgpu::Buffer)>,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Uniforms {
+    pub fn new(device: &wgpu::Device, buffers: Vec<(u32, wgpu::Buffer)>) -> Self {
+        let layout_entries: Vec<wgpu::BindGroupLayoutEntry> = buffers
+            .iter()
+            .map(|(binding, _)| wgpu::BindGroupLayoutEntry {
+                binding: *binding,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            })
+            .collect();
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &layout_entries,
+            label: Some("Uniform bind group layout"),
+        });
+
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &entries,
+            label: Some("Uniform Bind Group"),
+        });
+
+        Uniforms {
+            buffers,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    pub fn buffer(&self, binding: u32) -> Option<&wgpu::Buffer> {
+        self.buffers
+            .iter()
+            .find(|(bound, _)| *bound == binding)
+            .map(|(_, buffer)| buffer)
+    }
+
+    ///Returns the buffers so more can be added, rebuilding the bind group
+    pub fn into_buffers(self) -> Vec<(u32, wgpu::Buffer)> {
+        self.buffers
+    }
+}
+
+///Refers to a uniform bound with `Graphics::bind_uniform`, used to change
+///its value with `Graphics::update_uniform`.
+///It points at the binding of the pipeline, so binding it again with
+///`bind_uniform` makes the handle write to the new buffer.
+pub struct UniformHandle<T> {
+    pub(crate) pipeline_index: usize,
+    pub(crate) binding: u32,
+    marker: PhantomData<fn(T)>,
+}
+
+impl<T> UniformHandle<T> {
+    pub(crate) fn new(pipeline_index: usize, binding: u32) -> Self {
+        UniformHandle {
+            pipeline_index,
+            binding,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for UniformHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UniformHandle<T> {}
+
+impl<T> fmt::Debug for UniformHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "UniformHandle<{}>(pipeline={}, binding={})",
+            std::any::type_name::<T>(),
+            self.pipeline_index,
+            self.binding
+        )
+    }
+}
@@ -1,3 +1,27 @@
+///Describes how a vertex type is laid out in a vertex buffer, so meshes and
+///pipelines can be created for any vertex type with normals, colors or
+///skinning weights, see `Graphics::create_pipeline_with_layout`.
+///`ATTRIBUTES` is usually built with `wgpu::vertex_attr_array!`. The
+///position is expected at shader location 0, that's where the error shader
+///reads it from when a shader fails to compile.
+pub trait VertexLayout: bytemuck::Pod {
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+
+    ///Position of the vertex, used to compute the bounds of meshes.
+    ///Vertices without one give empty bounds.
+    fn position(&self) -> Option<[f32; 3]> {
+        None
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
@@ -5,17 +29,20 @@ pub struct Vertex {
     pub tex_coord: [f32; 2],
 }
 
-impl Vertex {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2];
-
-    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
-        use std::mem;
+impl VertexLayout for Vertex {
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute] =
+        &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2];
 
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRIBUTES,
-        }
+    fn position(&self) -> Option<[f32; 3]> {
+        Some(self.position)
     }
 }
+
+///Format of the attribute at shader location 0 of `layout`
+pub(crate) fn position_format(layout: &wgpu::VertexBufferLayout) -> Option<wgpu::VertexFormat> {
+    layout
+        .attributes
+        .iter()
+        .find(|attribute| attribute.shader_location == 0)
+        .map(|attribute| attribute.format)
+}
@@ -1,39 +1,95 @@
-use std::{env, time::Instant};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
-use log::info;
+use image::RgbaImage;
+use log::{error, info};
 use winit::{
     dpi::PhysicalSize,
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
     window::Fullscreen,
 };
 
 use self::{
-    camera::camera_controller::CameraController, config::Config, graphics::Graphics, scene::Scene,
-    scene_manager::scene_manager::SceneManager, window::Window,
+    assets::{
+        pack::{AssetPack, DEFAULT_ASSET_PACK},
+        AssetRoot,
+    },
+    camera::camera_controller::CameraController,
+    config::Config,
+    error::GenesisError,
+    graphics::Graphics,
+    scene::Scene,
+    scene_manager::scene_manager::SceneManager,
+    timestep::FixedTimestep,
+    window::Window,
 };
 
+pub mod animation;
+pub mod assets;
 pub mod camera;
 pub mod config;
+pub mod error;
 pub mod graphics;
 pub mod scene;
 mod scene_manager;
+pub mod snapshot;
+pub mod timestep;
 mod window;
 
+///Where the engine presents its frames: a real window driven by an event
+///loop, or an offscreen texture of the given size
+enum Target {
+    Window(Box<Window>),
+    Headless(PhysicalSize<u32>),
+}
+
+const DEFAULT_SCREENSHOT_KEY: KeyCode = KeyCode::F12;
+const SCREENSHOT_DIR: &str = "screenshots";
+
 pub struct Engine {
-    window: Window,
+    target: Target,
     scene_manager: SceneManager,
+    screenshot_key: Option<KeyCode>,
+    timestep: FixedTimestep,
+    asset_root: AssetRoot,
+    asset_pack: Option<PathBuf>,
+    sample_count: u32,
 }
 
 impl Engine {
     pub fn new() -> Self {
-        let window = Window::default();
-        let scene_manager = SceneManager::new();
+        Self::try_new().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new() -> Result<Self, GenesisError> {
+        let window = Window::try_default()?;
 
         info!("Engine created with default configuration.");
 
+        Ok(Self::from_target(Target::Window(Box::new(window))))
+    }
+
+    ///Creates an engine without a window. Frames are rendered offscreen, so
+    ///the engine has to be driven with `run_frames` instead of `run`.
+    pub fn headless(width: u32, height: u32) -> Self {
+        info!("Headless engine created: {}x{}", width, height);
+
+        Self::from_target(Target::Headless(PhysicalSize::new(width, height)))
+    }
+
+    fn from_target(target: Target) -> Self {
         Engine {
-            window,
-            scene_manager,
+            target,
+            scene_manager: SceneManager::new(),
+            screenshot_key: Some(DEFAULT_SCREENSHOT_KEY),
+            timestep: FixedTimestep::default(),
+            asset_root: AssetRoot::default(),
+            asset_pack: None,
+            sample_count: 1,
         }
     }
 
@@ -47,34 +103,59 @@ impl Engine {
     }
 
     pub fn new_from(title: &str, width: u32, height: u32, fullscreen: bool) -> Self {
-        let config = Config::new(title, width, height, fullscreen);
-        let window = Window::new(config);
-        let scene_manager = SceneManager::new();
+        Self::try_new_from(title, width, height, fullscreen).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new_from(
+        title: &str,
+        width: u32,
+        height: u32,
+        fullscreen: bool,
+    ) -> Result<Self, GenesisError> {
+        Self::try_from_config(Config::new(title, width, height, fullscreen))
+    }
+
+    pub fn from_config(config: Config) -> Self {
+        Self::try_from_config(config).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_config(config: Config) -> Result<Self, GenesisError> {
+        let window = Window::try_new(&config)?;
 
         info!(
             "Engine created with custon configuration: title={} widht={} height={} fullscreen={}",
-            title, width, height, fullscreen
+            config.title, config.width, config.height, config.fullscreen
         );
 
-        Engine {
-            window,
-            scene_manager,
+        let mut engine = Self::from_target(Target::Window(Box::new(window)));
+        if let Some(asset_root) = config.asset_root {
+            engine.asset_root = AssetRoot::new(asset_root);
         }
+        engine.asset_pack = config.asset_pack;
+        engine.sample_count = config.sample_count;
+
+        Ok(engine)
     }
 
     pub fn with_title(self, title: &str) -> Self {
-        self.window.window.set_title(title);
+        if let Target::Window(ref window) = self.target {
+            window.window.set_title(title);
+        }
 
         info!("Window's title set to: {}", title);
 
         self
     }
 
-    pub fn with_dimensions(self, width: u32, height: u32) -> Self {
-        let _ = self
-            .window
-            .window
-            .request_inner_size(PhysicalSize::new(width, height));
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        match self.target {
+            Target::Window(ref window) => {
+                let _ = window
+                    .window
+                    .request_inner_size(PhysicalSize::new(width, height));
+            }
+            Target::Headless(ref mut size) => *size = PhysicalSize::new(width, height),
+        }
 
         info!("Window's dimensions set to: {}x{}", width, height);
 
@@ -82,15 +163,82 @@ impl Engine {
     }
 
     pub fn fullscreen(self) -> Self {
-        self.window
-            .window
-            .set_fullscreen(Some(Fullscreen::Borderless(None)));
+        if let Target::Window(ref window) = self.target {
+            window
+                .window
+                .set_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
 
         info!("Fullscreen activated");
 
         self
     }
 
+    ///Sets the directory relative asset paths are resolved from.
+    ///Relative roots are relative to the executable, and the
+    ///`GENESIS_ASSET_ROOT` environment variable takes precedence over it.
+    pub fn with_asset_root<P: AsRef<Path>>(mut self, asset_root: P) -> Self {
+        self.asset_root.set_root(&asset_root);
+
+        info!("Asset root set to: {:?}", asset_root.as_ref());
+
+        self
+    }
+
+    ///Sets the asset pack assets are read from, resolved like any other
+    ///asset path. Assets missing from the pack are still loaded from the
+    ///filesystem. Without it, `assets.gpak` is used if it exists in the
+    ///asset root.
+    pub fn with_asset_pack<P: AsRef<Path>>(mut self, asset_pack: P) -> Self {
+        self.asset_pack = Some(asset_pack.as_ref().to_path_buf());
+
+        info!("Asset pack set to: {:?}", asset_pack.as_ref());
+
+        self
+    }
+
+    ///Embeds `bytes` as the contents of the asset at `file_path`, so the
+    ///game can be shipped as a single executable:
+    ///`.with_embedded_asset("player.png", include_bytes!("../assets/player.png"))`
+    pub fn with_embedded_asset(mut self, file_path: &str, bytes: &'static [u8]) -> Self {
+        self.asset_root.embed(file_path, bytes);
+
+        info!("Asset embedded: {} ({} bytes)", file_path, bytes.len());
+
+        self
+    }
+
+    ///Enables MSAA with `sample_count` samples per pixel (2, 4 or 8), which
+    ///smooths the edges of geometry. Counts the adapter doesn't support fall
+    ///back to the closest lower one it does, and 1 disables it.
+    pub fn with_msaa(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+
+        info!("MSAA sample count set to: {}", sample_count);
+
+        self
+    }
+
+    ///Sets the key that saves a screenshot of the current frame into the
+    ///`screenshots` directory while running. `None` disables the hotkey.
+    pub fn with_screenshot_key(mut self, key: Option<KeyCode>) -> Self {
+        self.screenshot_key = key;
+
+        info!("Screenshot key set to: {:?}", key);
+
+        self
+    }
+
+    ///Sets how many times per second `Scene::fixed_update` runs and the
+    ///maximum number of fixed steps a single frame can catch up on
+    pub fn with_fixed_timestep(mut self, rate: f32, max_steps: u32) -> Self {
+        self.timestep = FixedTimestep::new(rate, max_steps);
+
+        info!("Fixed timestep set to: {}Hz max_steps={}", rate, max_steps);
+
+        self
+    }
+
     pub fn add_scene<S: Scene + 'static>(mut self, label: &str, scene: S) -> Self {
         self.scene_manager.add_scene(label, scene);
 
@@ -104,72 +252,212 @@ impl Engine {
         self
     }
 
-    pub fn run(mut self) {
-        let mut graphics = Graphics::new(&self.window.window);
+    pub fn run(self) {
+        self.try_run().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    ///Same as `run` but returns an error when the window, graphics or event
+    ///loop could not be created, instead of panicking
+    pub fn try_run(mut self) -> Result<(), GenesisError> {
+        let asset_root = self.open_asset_root()?;
+        let Target::Window(target) = self.target else {
+            return Err(GenesisError::Headless);
+        };
+        let Window { window, event_loop } = *target;
+        let window = &window;
 
-        let mut camera_controller = CameraController::new(0.5);
+        let mut graphics = Graphics::try_new(window)?;
+        graphics.set_asset_root(asset_root);
+        graphics.set_sample_count(self.sample_count);
+
+        let camera_controller = CameraController::new(0.5);
 
         let mut last_frame_time = Instant::now();
 
-        self.window
-            .event_loop
-            .run(move |event, target| {
+        event_loop.run(move |event, target| match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => target.exit(),
+
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => {
                 let now = Instant::now();
-                let delta_time = now.duration_since(last_frame_time);
+                let delta_secs = now.duration_since(last_frame_time).as_secs_f32();
                 last_frame_time = now;
-                let delta_secs = delta_time.as_secs_f32();
 
-                match event {
-                    Event::WindowEvent {
-                        event: WindowEvent::CloseRequested,
+                frame(
+                    &mut self.scene_manager,
+                    &mut graphics,
+                    &camera_controller,
+                    &mut self.timestep,
+                    delta_secs,
+                );
+
+                window.request_redraw();
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => {
+                info!("Resize requested for: {}x{}", size.width, size.height);
+
+                graphics.resize(size.width, size.height);
+            }
+
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(key),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
                         ..
-                    } => target.exit(),
+                    },
+                ..
+            } if Some(key) == self.screenshot_key => save_screenshot(&graphics),
 
-                    Event::WindowEvent {
-                        event: WindowEvent::RedrawRequested,
-                        ..
-                    } => {
-                        camera_controller.update_camera(&mut graphics.camera);
-                        graphics.camera_uniform.update_view_proj(&graphics.camera);
-                        graphics.queue.write_buffer(
-                            &graphics.camera_buffer,
-                            0,
-                            bytemuck::cast_slice(&[graphics.camera_uniform]),
-                        );
+            Event::WindowEvent { event, .. } => {
+                if let Some(scene) = self.scene_manager.get_active_scene() {
+                    scene.input(&event, &target);
+                }
+            }
+            _ => (),
+        })?;
 
-                        graphics.render();
+        Ok(())
+    }
 
-                        if let Some(scene) = self.scene_manager.get_active_scene() {
-                            scene.update(delta_secs);
-                            scene.draw(&mut graphics);
-                        }
+    ///Drives the engine for a fixed number of `frames` without an event loop,
+    ///passing the same `delta_time` to every update. Headless engines render
+    ///offscreen, which makes this usable on machines without a display.
+    pub fn run_frames(self, frames: u32, delta_time: f32) {
+        self.try_run_frames(frames, delta_time)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-                        self.scene_manager.update(&mut graphics);
+    pub fn try_run_frames(mut self, frames: u32, delta_time: f32) -> Result<(), GenesisError> {
+        self.drive_frames(frames, delta_time, |_| ())
+    }
 
-                        graphics.window.request_redraw();
-                    }
+    ///Same as `run_frames`, but reads the last rendered frame back from the
+    ///GPU. Returns `None` when the engine is not headless.
+    pub fn capture_frames(self, frames: u32, delta_time: f32) -> Option<RgbaImage> {
+        self.try_capture_frames(frames, delta_time)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-                    Event::WindowEvent {
-                        event: WindowEvent::Resized(size),
-                        ..
-                    } => {
-                        info!("Resize requested for: {}x{}", size.width, size.height);
-
-                        graphics.config.width = size.width;
-                        graphics.config.height = size.height;
-                        graphics
-                            .surface
-                            .configure(&graphics.device, &graphics.config);
-                    }
-
-                    Event::WindowEvent { event, .. } => {
-                        if let Some(scene) = self.scene_manager.get_active_scene() {
-                            scene.input(&event, &target);
-                        }
-                    }
-                    _ => (),
-                }
-            })
-            .unwrap();
+    pub fn try_capture_frames(
+        mut self,
+        frames: u32,
+        delta_time: f32,
+    ) -> Result<Option<RgbaImage>, GenesisError> {
+        self.drive_frames(frames, delta_time, |graphics| graphics.read_frame())
     }
+
+    ///Returns the asset root with the configured asset pack opened, or the
+    ///default one when it exists
+    fn open_asset_root(&self) -> Result<AssetRoot, GenesisError> {
+        let mut asset_root = self.asset_root.clone();
+
+        let pack_path = match &self.asset_pack {
+            Some(asset_pack) => Some(asset_root.resolve(&asset_pack.to_string_lossy())),
+            None => Some(asset_root.resolve(DEFAULT_ASSET_PACK)).filter(|path| path.is_file()),
+        };
+
+        if let Some(pack_path) = pack_path {
+            asset_root.set_pack(AssetPack::open(pack_path)?);
+        }
+
+        Ok(asset_root)
+    }
+
+    fn drive_frames<R>(
+        &mut self,
+        frames: u32,
+        delta_time: f32,
+        finish: impl FnOnce(&mut Graphics) -> R,
+    ) -> Result<R, GenesisError> {
+        let mut graphics = match self.target {
+            Target::Window(ref window) => Graphics::try_new(&window.window)?,
+            Target::Headless(size) => Graphics::try_new_headless(size.width, size.height)?,
+        };
+        graphics.set_asset_root(self.open_asset_root()?);
+        graphics.set_sample_count(self.sample_count);
+
+        let camera_controller = CameraController::new(0.5);
+
+        for _ in 0..frames {
+            frame(
+                &mut self.scene_manager,
+                &mut graphics,
+                &camera_controller,
+                &mut self.timestep,
+                delta_time,
+            );
+        }
+
+        info!("Finished running {} frames", frames);
+
+        Ok(finish(&mut graphics))
+    }
+}
+
+///Saves the current frame into `SCREENSHOT_DIR` named after the current time
+fn save_screenshot(graphics: &Graphics) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let file_path = Path::new(SCREENSHOT_DIR).join(format!("screenshot-{}.png", timestamp));
+
+    if let Err(err) = fs::create_dir_all(SCREENSHOT_DIR) {
+        error!("Unable to create {}: {}", SCREENSHOT_DIR, err);
+        return;
+    }
+
+    if let Err(err) = graphics.capture_frame(&file_path) {
+        error!("Unable to save screenshot {:?}: {}", file_path, err);
+    }
+}
+
+///Runs a single frame: updates the camera, runs the fixed steps accumulated
+///since the last frame, updates and draws the active scene, renders what it
+///drew and processes any pending scene change
+fn frame(
+    scene_manager: &mut SceneManager,
+    graphics: &mut Graphics,
+    camera_controller: &CameraController,
+    timestep: &mut FixedTimestep,
+    delta_secs: f32,
+) {
+    camera_controller.update_camera(&mut graphics.camera);
+    graphics.camera_uniform.update_view_proj(&graphics.camera);
+    graphics.queue.write_buffer(
+        &graphics.camera_buffer,
+        0,
+        bytemuck::cast_slice(&[graphics.camera_uniform]),
+    );
+
+    graphics.update_assets();
+
+    let steps = timestep.advance(delta_secs);
+
+    if let Some(scene) = scene_manager.get_active_scene() {
+        for _ in 0..steps {
+            scene.fixed_update(timestep.step());
+        }
+
+        scene.update(delta_secs);
+        scene.draw(graphics, timestep.alpha());
+    }
+
+    graphics.render();
+    scene_manager.update(graphics);
 }
@@ -6,15 +6,21 @@ use super::graphics::Graphics;
 ///- `init`: Gets called once and is ment to allocate the required resources and run initialization
 ///logic
 ///- `input`: Gets called every loop with an accessible `WindowEvent`
+///- `fixed_update`: Gets called zero or more times every loop with a constant `fixed_delta`, so
+///physics and gameplay advance the same way at any frame rate. Does nothing by default
 ///- `update`: Gets called every loop with an accessible `delta_time`
-///- `draw`: Gets called every loop with access to the `graphics`
+///- `draw`: Gets called every loop with access to the `graphics` and an `alpha` in `0.0..1.0`
+///telling how far the current frame is between the last and the next fixed update, used to
+///interpolate state for rendering. The frame is rendered right after, so draws queued with
+///`Graphics::queue_draw` show up in it
 ///- `cleanup`: Gets called once when switching to a diffent scene.
 ///- `is_initialized`: Is a flag that inidicates if the `init` function has been called or not
 pub trait Scene {
     fn init(&mut self, graphics: &mut Graphics);
     fn input(&mut self, event: &WindowEvent, target: &EventLoopWindowTarget<()>);
+    fn fixed_update(&mut self, _fixed_delta: f32) {}
     fn update(&mut self, delta_time: f32);
-    fn draw(&self, graphics: &mut Graphics);
+    fn draw(&self, graphics: &mut Graphics, alpha: f32);
     fn cleanup(&mut self);
     fn is_initialized(&self) -> bool;
 }
@@ -0,0 +1,239 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use image::{Rgba, RgbaImage};
+use log::info;
+
+use super::{error::GenesisError, scene::Scene, Engine};
+
+///When this environment variable is set, golden images are overwritten with
+///the rendered frame instead of being compared against it
+pub const UPDATE_SNAPSHOTS_ENV: &str = "GENESIS_UPDATE_SNAPSHOTS";
+
+const SNAPSHOT_SCENE: &str = "snapshot";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Engine(GenesisError),
+    Image(image::ImageError),
+    NoFrame,
+    MissingGolden {
+        golden_path: PathBuf,
+        actual_path: PathBuf,
+    },
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    Mismatch {
+        differing_pixels: usize,
+        diff_path: PathBuf,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Engine(err) => write!(f, "Unable to run the scene: {}", err),
+            SnapshotError::Image(err) => write!(f, "Unable to read or write snapshot: {}", err),
+            SnapshotError::NoFrame => write!(f, "No frame could be read back from the engine"),
+            SnapshotError::MissingGolden {
+                golden_path,
+                actual_path,
+            } => write!(
+                f,
+                "Golden image {:?} not found, frame written to {:?}. Set {} to store it",
+                golden_path, actual_path, UPDATE_SNAPSHOTS_ENV
+            ),
+            SnapshotError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Snapshot size mismatch: expected {}x{}, got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            SnapshotError::Mismatch {
+                differing_pixels,
+                diff_path,
+            } => write!(
+                f,
+                "{} pixels differ from the golden image, diff written to {:?}",
+                differing_pixels, diff_path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<GenesisError> for SnapshotError {
+    fn from(err: GenesisError) -> Self {
+        SnapshotError::Engine(err)
+    }
+}
+
+impl From<image::ImageError> for SnapshotError {
+    fn from(err: image::ImageError) -> Self {
+        SnapshotError::Image(err)
+    }
+}
+
+///Golden-image test harness for scenes.
+///Runs a `Scene` on a headless engine for a number of frames with a fixed
+///delta, reads the last frame back and compares it against a stored PNG.
+///The first frame only initializes the scene, so at least two frames are
+///needed to see anything it registers in `init`.
+///```ignore
+///Snapshot::new(MyScene::new())
+///    .with_dimensions(320, 240)
+///    .with_frames(3)
+///    .with_tolerance(2)
+///    .assert_matches("tests/snapshots/my_scene.png");
+///```
+pub struct Snapshot<S: Scene + 'static> {
+    scene: S,
+    width: u32,
+    height: u32,
+    frames: u32,
+    delta_time: f32,
+    tolerance: u8,
+}
+
+impl<S: Scene + 'static> Snapshot<S> {
+    ///Creates a snapshot of 256x256 pixels rendered after 2 frames at 60 fps
+    ///with no per-pixel tolerance
+    pub fn new(scene: S) -> Self {
+        Snapshot {
+            scene,
+            width: 256,
+            height: 256,
+            frames: 2,
+            delta_time: 1.0 / 60.0,
+            tolerance: 0,
+        }
+    }
+
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+
+        self
+    }
+
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+
+        self
+    }
+
+    pub fn with_delta(mut self, delta_time: f32) -> Self {
+        self.delta_time = delta_time;
+
+        self
+    }
+
+    ///Maximum difference allowed on each color channel before a pixel is
+    ///considered different
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+
+        self
+    }
+
+    ///Runs the scene and returns the last rendered frame
+    pub fn render(self) -> Result<RgbaImage, SnapshotError> {
+        Engine::headless(self.width, self.height)
+            .add_scene(SNAPSHOT_SCENE, self.scene)
+            .switch_scene(SNAPSHOT_SCENE)
+            .try_capture_frames(self.frames, self.delta_time)?
+            .ok_or(SnapshotError::NoFrame)
+    }
+
+    ///Renders the scene and compares it against the PNG at `golden_path`.
+    ///If `GENESIS_UPDATE_SNAPSHOTS` is set, the rendered frame is stored as
+    ///the new golden image instead. A missing golden image is an error, so a
+    ///mistyped path can't pass by silently creating one.
+    ///On mismatch a diff image is written next to the golden image.
+    pub fn compare<P: AsRef<Path>>(self, golden_path: P) -> Result<(), SnapshotError> {
+        let golden_path = golden_path.as_ref();
+        let tolerance = self.tolerance;
+        let actual = self.render()?;
+
+        if env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+            info!("Writing golden image: {:?}", golden_path);
+            actual.save(golden_path)?;
+            return Ok(());
+        }
+
+        if !golden_path.exists() {
+            let actual_path = golden_path.with_extension("actual.png");
+            actual.save(&actual_path)?;
+
+            return Err(SnapshotError::MissingGolden {
+                golden_path: golden_path.to_path_buf(),
+                actual_path,
+            });
+        }
+
+        let expected = image::open(golden_path)?.to_rgba8();
+
+        if expected.dimensions() != actual.dimensions() {
+            return Err(SnapshotError::SizeMismatch {
+                expected: expected.dimensions(),
+                actual: actual.dimensions(),
+            });
+        }
+
+        let (diff, differing_pixels) = diff_images(&expected, &actual, tolerance);
+
+        if differing_pixels == 0 {
+            return Ok(());
+        }
+
+        let diff_path = golden_path.with_extension("diff.png");
+        diff.save(&diff_path)?;
+        actual.save(golden_path.with_extension("actual.png"))?;
+
+        Err(SnapshotError::Mismatch {
+            differing_pixels,
+            diff_path,
+        })
+    }
+
+    ///Same as `compare` but panics on failure, meant to be used inside tests
+    pub fn assert_matches<P: AsRef<Path>>(self, golden_path: P) {
+        let golden_path = golden_path.as_ref();
+
+        if let Err(err) = self.compare(golden_path) {
+            panic!("Snapshot {:?} failed: {}", golden_path, err);
+        }
+    }
+}
+
+///Builds an image where differing pixels are painted red over a faded copy of
+///the actual frame, and returns it along with the number of differing pixels
+fn diff_images(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut differing_pixels = 0;
+
+    for (x, y, actual_pixel) in actual.enumerate_pixels() {
+        let expected_pixel = expected.get_pixel(x, y);
+        let differs = actual_pixel
+            .0
+            .iter()
+            .zip(expected_pixel.0.iter())
+            .any(|(a, e)| a.abs_diff(*e) > tolerance);
+
+        let pixel = if differs {
+            differing_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = actual_pixel.0;
+            Rgba([r / 4, g / 4, b / 4, 255])
+        };
+
+        diff.put_pixel(x, y, pixel);
+    }
+
+    (diff, differing_pixels)
+}
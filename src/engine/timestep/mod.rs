@@ -0,0 +1,97 @@
+pub const DEFAULT_FIXED_RATE: f32 = 60.0;
+pub const DEFAULT_MAX_STEPS: u32 = 5;
+
+///Accumulates frame time and splits it into steps of a fixed size so the
+///simulation advances the same way regardless of the frame rate.
+///- `advance`: Adds the frame delta and returns how many fixed steps to run,
+///never more than `max_steps` so a slow frame can't snowball into slower ones
+///- `alpha`: How far the leftover time is into the next step, in `0.0..1.0`,
+///meant to interpolate between the previous and current simulation state
+pub struct FixedTimestep {
+    step: f32,
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: f32, max_steps: u32) -> Self {
+        FixedTimestep {
+            step: 1.0 / rate,
+            max_steps: max_steps.max(1),
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) -> u32 {
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if steps == self.max_steps && self.accumulator >= self.step {
+            self.accumulator %= self.step;
+        }
+
+        steps
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+
+    ///Size of a single step in seconds
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        FixedTimestep::new(DEFAULT_FIXED_RATE, DEFAULT_MAX_STEPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_time_into_steps() {
+        let mut timestep = FixedTimestep::new(4.0, 5);
+
+        assert_eq!(timestep.advance(0.125), 0);
+        assert_eq!(timestep.advance(0.125), 1);
+        assert_eq!(timestep.advance(0.625), 2);
+        assert_eq!(timestep.advance(0.125), 1);
+    }
+
+    #[test]
+    fn clamps_steps_and_drops_whole_steps_past_the_limit() {
+        let mut timestep = FixedTimestep::new(4.0, 3);
+
+        assert_eq!(timestep.advance(10.125), 3);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+
+    #[test]
+    fn alpha_is_the_fraction_of_the_next_step() {
+        let mut timestep = FixedTimestep::new(4.0, 5);
+
+        assert_eq!(timestep.alpha(), 0.0);
+        timestep.advance(0.0625);
+        assert_eq!(timestep.alpha(), 0.25);
+        timestep.advance(0.3125);
+        assert_eq!(timestep.alpha(), 0.5);
+    }
+
+    #[test]
+    fn runs_at_least_one_step() {
+        let mut timestep = FixedTimestep::new(4.0, 0);
+
+        assert_eq!(timestep.advance(1.0), 1);
+    }
+}
@@ -4,7 +4,7 @@ use winit::{
     window::{Fullscreen, WindowBuilder},
 };
 
-use super::config::Config;
+use super::{config::Config, error::GenesisError};
 
 pub struct Window {
     pub window: winit::window::Window,
@@ -12,30 +12,35 @@ pub struct Window {
 }
 
 impl Window {
-    pub fn new(config: Config) -> Self {
-        let event_loop = EventLoop::new().unwrap();
+    pub fn try_new(config: &Config) -> Result<Self, GenesisError> {
+        let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(event_loop::ControlFlow::Poll);
         let mut window_builder = WindowBuilder::new()
-            .with_title(config.title)
+            .with_title(&config.title)
             .with_inner_size(Size::new(PhysicalSize::new(config.width, config.height)));
 
         if config.fullscreen {
             window_builder = window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
         }
 
-        let window = window_builder.build(&event_loop).unwrap();
+        let window = window_builder.build(&event_loop)?;
 
-        Window { window, event_loop }
+        Ok(Window { window, event_loop })
+    }
+
+    ///Same as `Window::default` but returns an error instead of panicking
+    pub fn try_default() -> Result<Self, GenesisError> {
+        let event_loop = EventLoop::new()?;
+        event_loop.set_control_flow(event_loop::ControlFlow::Poll);
+        let window = WindowBuilder::new().build(&event_loop)?;
+
+        Ok(Window { window, event_loop })
     }
 }
 
 ///This will create a Window with all the default values
 impl Default for Window {
     fn default() -> Self {
-        let event_loop = EventLoop::new().unwrap();
-        event_loop.set_control_flow(event_loop::ControlFlow::Poll);
-        let window = WindowBuilder::new().build(&event_loop).unwrap();
-
-        Window { window, event_loop }
+        Self::try_default().unwrap_or_else(|err| panic!("{}", err))
     }
 }
@@ -1,9 +1,14 @@
 pub mod engine;
 pub use bytemuck;
+pub use engine::error::GenesisError;
 
 pub mod prelude {
     pub use crate::engine;
+    pub use crate::engine::animation::{PlaybackMode, SpriteAnimation};
     pub use crate::engine::camera;
+    pub use crate::engine::error::GenesisError;
+    pub use crate::engine::graphics::sprite::{Sprite, SpriteBatch};
+    pub use crate::engine::graphics::vertex_buffers::VertexLayout;
     pub use crate::engine::graphics::Graphics;
     pub use crate::engine::scene::Scene;
     pub use bytemuck::{self};
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use genesis_engine::{engine::snapshot::Snapshot, prelude::*};
+
+struct ClearColorScene {
+    initialized: bool,
+}
+
+impl Scene for ClearColorScene {
+    fn init(&mut self, graphics: &mut Graphics) {
+        graphics.clear_color = Color {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        self.initialized = true;
+    }
+
+    fn input(&mut self, _event: &WindowEvent, _target: &EventLoopWindowTarget<()>) {}
+
+    fn update(&mut self, _delta_time: f32) {}
+
+    fn draw(&self, _graphics: &mut Graphics, _alpha: f32) {}
+
+    fn cleanup(&mut self) {}
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name)
+}
+
+#[test]
+fn clear_color_matches_golden() {
+    Snapshot::new(ClearColorScene { initialized: false })
+        .with_dimensions(64, 64)
+        .assert_matches(golden_path("clear_color.png"));
+}